@@ -0,0 +1,189 @@
+//! Column (block) selection.
+//!
+//! A block selection is a rectangle of document lines and display columns.
+//! The editor realizes it as one cursor per line, each selecting the columns
+//! of the rectangle that exist on its line, so typing, deleting and copying
+//! work through the multi-cursor paths. Columns are measured with
+//! `wrap::char_width`, so wide characters take two and combining marks none,
+//! matching what is drawn in the monospace document font. A column that falls
+//! inside a wide character snaps to its start, and a line shorter than the
+//! rectangle gets an empty selection at its end.
+
+use std::ops::Range;
+
+use crate::cursors::Cursor;
+use crate::wrap;
+
+/// A document line and display column.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    /// Line and column of the byte `offset`.
+    pub fn of(content: &str, offset: usize) -> Self {
+        let before = &content[..offset];
+        let start = before.rfind('\n').map_or(0, |idx| idx + 1);
+        Self {
+            line: before.matches('\n').count(),
+            column: wrap::str_width(&before[start..]),
+        }
+    }
+}
+
+/// A rectangular selection from the corner where it started to the one that moves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlockSelection {
+    pub anchor: Position,
+    pub head: Position,
+}
+
+impl BlockSelection {
+    /// An empty block at `offset`.
+    pub fn at(content: &str, offset: usize) -> Self {
+        let position = Position::of(content, offset);
+        Self {
+            anchor: position,
+            head: position,
+        }
+    }
+
+    /// The block with its head moved by `lines` and `columns`, kept inside the document.
+    pub fn moved(self, content: &str, lines: isize, columns: isize) -> Self {
+        let last_line = content.matches('\n').count();
+        Self {
+            anchor: self.anchor,
+            head: Position {
+                line: self.head.line.saturating_add_signed(lines).min(last_line),
+                column: self.head.column.saturating_add_signed(columns),
+            },
+        }
+    }
+
+    /// One cursor per line, with the head on the head's column side; the
+    /// cursor on the head's line is flagged as primary.
+    pub fn cursors(&self, content: &str) -> Vec<(Cursor, bool)> {
+        let lines = self.anchor.line.min(self.head.line)..=self.anchor.line.max(self.head.line);
+        lines
+            .filter_map(|line| {
+                let bounds = line_bounds(content, line)?;
+                let anchor = offset_in_line(content, &bounds, self.anchor.column);
+                let head = offset_in_line(content, &bounds, self.head.column);
+                let cursor = Cursor {
+                    head,
+                    anchor: (anchor != head).then_some(anchor),
+                };
+                Some((cursor, line == self.head.line))
+            })
+            .collect()
+    }
+}
+
+/// Byte range of `line`, without its line break.
+fn line_bounds(content: &str, line: usize) -> Option<Range<usize>> {
+    let start = if line == 0 {
+        0
+    } else {
+        content.match_indices('\n').nth(line - 1)?.0 + 1
+    };
+    let end = content[start..]
+        .find('\n')
+        .map_or(content.len(), |idx| start + idx);
+    Some(start..end)
+}
+
+/// Offset of `column` within the line at `bounds`, clamped to the line's end.
+fn offset_in_line(content: &str, bounds: &Range<usize>, column: usize) -> usize {
+    let mut width = 0;
+    for (idx, ch) in content[bounds.clone()].char_indices() {
+        width += wrap::char_width(ch);
+        // Stop before a character that would end past the column
+        if width > column {
+            return bounds.start + idx;
+        }
+    }
+    bounds.end
+}
+
+/// Inserts `rows` as a block: each row at `at`'s column on successive lines.
+///
+/// Lines shorter than the column are padded with spaces and lines past the end
+/// of the document are added. Returns the offset after the last inserted row.
+pub fn insert_block(content: &mut String, at: usize, rows: &[&str]) -> usize {
+    let position = Position::of(content, at);
+    let mut end = at;
+    for (index, row) in rows.iter().enumerate() {
+        let line = position.line + index;
+        let bounds = match line_bounds(content, line) {
+            Some(bounds) => bounds,
+            None => {
+                content.push('\n');
+                content.len()..content.len()
+            }
+        };
+        let length = wrap::str_width(&content[bounds.clone()]);
+        let offset = if length < position.column {
+            let padding = " ".repeat(position.column - length);
+            content.insert_str(bounds.end, &padding);
+            bounds.end + padding.len()
+        } else {
+            offset_in_line(content, &bounds, position.column)
+        };
+        content.insert_str(offset, row);
+        end = offset + row.len();
+    }
+    end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn columns_count_display_width() {
+        let content = "a界b\ne\u{301}x";
+        assert_eq!(Position::of(content, 4), Position { line: 0, column: 3 });
+        assert_eq!(Position::of(content, 9), Position { line: 1, column: 1 });
+    }
+
+    #[test]
+    fn block_cursors_line_up_across_wide_characters() {
+        let content = "abcd\n界界\nab";
+        let block = BlockSelection {
+            anchor: Position { line: 0, column: 1 },
+            head: Position { line: 2, column: 3 },
+        };
+        let cursors: Vec<Cursor> = block
+            .cursors(content)
+            .into_iter()
+            .map(|(cursor, _)| cursor)
+            .collect();
+        assert_eq!(
+            cursors,
+            vec![
+                Cursor {
+                    anchor: Some(1),
+                    head: 3
+                },
+                Cursor {
+                    anchor: Some(5),
+                    head: 8
+                },
+                Cursor {
+                    anchor: Some(13),
+                    head: 14
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn inserts_a_block_padding_by_display_width() {
+        let mut content = "界x\na".to_string();
+        let end = insert_block(&mut content, 3, &["1", "2", "3"]);
+        assert_eq!(content, "界1x\na 2\n  3");
+        assert_eq!(end, content.len());
+    }
+}
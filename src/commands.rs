@@ -0,0 +1,160 @@
+//! Registry of editor commands with display names and default keybindings.
+//!
+//! This table is the single source of truth for keybindings: `main.rs` binds
+//! keys from it and the command palette lists it, so the shortcuts shown in the
+//! palette always match the ones that are active.
+
+use gpui::{Action, KeyBinding};
+
+use crate::editor::*;
+
+/// A user-facing editor command.
+#[derive(Clone, Copy)]
+pub struct Command {
+    /// Human-readable name shown in the command palette.
+    pub name: &'static str,
+    /// Keystrokes bound to the command, in GPUI keystroke syntax (e.g. `ctrl-shift-p`).
+    pub keys: &'static [&'static str],
+    /// Builds the action dispatched when the command runs.
+    pub action: fn() -> Box<dyn Action>,
+}
+
+impl Command {
+    /// Keybindings formatted for display, e.g. `Ctrl+Shift+P`.
+    pub fn keys_label(&self) -> String {
+        self.keys
+            .iter()
+            .map(|keys| format_keystrokes(keys))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Declares the command table and derives both the palette list and the keymap from it.
+macro_rules! commands {
+    ($($action:ident => $name:literal, [$($key:literal),*];)*) => {
+        /// Every editor action with its palette name and keybindings.
+        pub fn all() -> Vec<Command> {
+            vec![$(
+                Command {
+                    name: $name,
+                    keys: &[$($key),*],
+                    action: || Box::new($action),
+                },
+            )*]
+        }
+
+        /// Keybindings for every command in the table.
+        pub fn key_bindings() -> Vec<KeyBinding> {
+            vec![$($(KeyBinding::new($key, $action, None),)*)*]
+        }
+    };
+}
+
+commands! {
+    MoveLeft => "Move Left", ["left"];
+    MoveRight => "Move Right", ["right"];
+    MoveUp => "Move Up", ["up"];
+    MoveDown => "Move Down", ["down"];
+    Backspace => "Delete Backward", ["backspace"];
+    Enter => "Insert Newline", ["enter"];
+    Save => "Save File", ["ctrl-s"];
+    Quit => "Quit", ["ctrl-q"];
+    Copy => "Copy", ["ctrl-c"];
+    Paste => "Paste", ["ctrl-v"];
+    Cut => "Cut", ["ctrl-x"];
+    SelectLeft => "Select Left", ["shift-left"];
+    SelectRight => "Select Right", ["shift-right"];
+    SelectUp => "Select Up", ["shift-up"];
+    SelectDown => "Select Down", ["shift-down"];
+    SelectAll => "Select All", ["ctrl-a"];
+    ToggleFind => "Find / Replace", ["ctrl-f"];
+    FindNext => "Find Next", ["f3"];
+    FindPrevious => "Find Previous", ["shift-f3"];
+    TogglePalette => "Go to File", ["ctrl-p"];
+    ToggleCommandPalette => "Command Palette", ["ctrl-shift-p"];
+    GoToSymbol => "Go to Symbol", ["ctrl-shift-o"];
+    ToggleOutline => "Toggle Outline", ["ctrl-alt-o"];
+    MoveSectionUp => "Move Section Up", ["ctrl-k ctrl-up"];
+    MoveSectionDown => "Move Section Down", ["ctrl-k ctrl-down"];
+    PromoteSection => "Promote Section", ["ctrl-k ctrl-left"];
+    DemoteSection => "Demote Section", ["ctrl-k ctrl-right"];
+    ToggleFileTree => "Toggle File Tree", ["ctrl-shift-e"];
+    Fold => "Fold", ["ctrl-shift-["];
+    Unfold => "Unfold", ["ctrl-shift-]"];
+    FoldAll => "Fold All", ["ctrl-k ctrl-0"];
+    UnfoldAll => "Unfold All", ["ctrl-k ctrl-j"];
+    MoveHome => "Move to Line Start", ["home"];
+    MoveEnd => "Move to Line End", ["end"];
+    ToggleSoftWrap => "Toggle Soft Wrap", ["alt-z"];
+    MoveWordLeft => "Move to Previous Word", ["ctrl-left"];
+    MoveWordRight => "Move to Next Word", ["ctrl-right"];
+    SelectWordLeft => "Select to Previous Word", ["ctrl-shift-left"];
+    SelectWordRight => "Select to Next Word", ["ctrl-shift-right"];
+    SelectHome => "Select to Line Start", ["shift-home"];
+    SelectEnd => "Select to Line End", ["shift-end"];
+    MoveToStart => "Move to Document Start", ["ctrl-home"];
+    MoveToEnd => "Move to Document End", ["ctrl-end"];
+    SelectToStart => "Select to Document Start", ["ctrl-shift-home"];
+    SelectToEnd => "Select to Document End", ["ctrl-shift-end"];
+    PageUp => "Page Up", ["pageup"];
+    PageDown => "Page Down", ["pagedown"];
+    SelectPageUp => "Select Page Up", ["shift-pageup"];
+    SelectPageDown => "Select Page Down", ["shift-pagedown"];
+    MoveParagraphUp => "Move to Previous Paragraph", ["ctrl-up"];
+    MoveParagraphDown => "Move to Next Paragraph", ["ctrl-down"];
+    SelectParagraphUp => "Select to Previous Paragraph", ["ctrl-shift-up"];
+    SelectParagraphDown => "Select to Next Paragraph", ["ctrl-shift-down"];
+    Delete => "Delete Forward", ["delete"];
+    DeleteWordBackward => "Delete Word Backward", ["ctrl-backspace"];
+    DeleteWordForward => "Delete Word Forward", ["ctrl-delete"];
+    DeleteLine => "Delete Line", ["ctrl-shift-k"];
+    DeleteToLineEnd => "Delete to End of Line", ["ctrl-k ctrl-k"];
+    AddNextOccurrence => "Add Next Occurrence", ["ctrl-d"];
+    AddCursorAbove => "Add Cursor Above", ["ctrl-alt-up"];
+    AddCursorBelow => "Add Cursor Below", ["ctrl-alt-down"];
+    SelectAllMatches => "Select All Occurrences", ["ctrl-shift-l", "alt-enter"];
+    ColumnSelectUp => "Column Select Up", ["alt-shift-up"];
+    ColumnSelectDown => "Column Select Down", ["alt-shift-down"];
+    ColumnSelectLeft => "Column Select Left", ["alt-shift-left"];
+    ColumnSelectRight => "Column Select Right", ["alt-shift-right"];
+    IndentLines => "Indent", ["tab"];
+    OutdentLines => "Outdent", ["shift-tab"];
+    MoveLineUp => "Move Line Up", ["alt-up"];
+    MoveLineDown => "Move Line Down", ["alt-down"];
+    DuplicateLine => "Duplicate Line", ["ctrl-shift-d"];
+    JoinLines => "Join Lines", ["ctrl-j"];
+    SortLines => "Sort Lines", [];
+    ReverseLines => "Reverse Lines", [];
+    UniqueLines => "Unique Lines", [];
+    TransposeChars => "Transpose Characters", ["ctrl-t"];
+}
+
+/// Formats a space-separated keystroke sequence like `ctrl-shift-p` as `Ctrl+Shift+P`.
+pub fn format_keystrokes(keys: &str) -> String {
+    keys.split(' ')
+        .map(|keystroke| {
+            keystroke
+                .split('-')
+                .map(|part| match part {
+                    "ctrl" => "Ctrl".to_string(),
+                    "shift" => "Shift".to_string(),
+                    "alt" => "Alt".to_string(),
+                    "cmd" | "super" | "win" => "Super".to_string(),
+                    "pageup" => "PageUp".to_string(),
+                    "pagedown" => "PageDown".to_string(),
+                    "" => "-".to_string(),
+                    other => {
+                        let mut chars = other.chars();
+                        match chars.next() {
+                            Some(first) => first.to_uppercase().chain(chars).collect(),
+                            None => String::new(),
+                        }
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("+")
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,229 @@
+//! User and project configuration.
+//!
+//! Settings are read from `$XDG_CONFIG_HOME/medleytext/config` (falling back to
+//! `~/.config/medleytext/config`) and then from `.medleytext` in the working
+//! directory, so project settings override user settings. Both files use a
+//! minimal `key = value` format with `#` comments:
+//!
+//! ```text
+//! # Lists are comma separated; globs use .gitignore syntax
+//! finder.extensions = md, markdown, txt
+//! finder.sniff_extensionless = true
+//! finder.exclude = drafts/, *.tmp.md
+//! finder.include = docs/**, notes/**
+//! finder.hidden = true
+//! finder.ignore_files = true
+//! editor.soft_wrap = true
+//! editor.wrap_column = 100
+//! editor.indent_width = 2
+//! editor.indent_tabs = false
+//! editor.detect_indent = true
+//! ```
+
+use std::path::{Path, PathBuf};
+
+/// File name of the project-level configuration file.
+pub const PROJECT_CONFIG_FILE: &str = ".medleytext";
+
+/// Extensions indexed when the configuration does not override them.
+const DEFAULT_EXTENSIONS: [&str; 6] = ["md", "markdown", "mdx", "txt", "org", "todo"];
+
+/// Settings for the fuzzy file finder's directory walk.
+#[derive(Clone, Debug)]
+pub struct FinderConfig {
+    /// Lowercase file extensions (without the dot) that are indexed.
+    pub extensions: Vec<String>,
+    /// Whether files without an extension are indexed when their content looks like text.
+    pub sniff_extensionless: bool,
+    /// Extra patterns to skip, in .gitignore syntax.
+    pub exclude: Vec<String>,
+    /// When non-empty, only files matching one of these patterns are listed.
+    pub include: Vec<String>,
+    /// Whether dot-files and dot-directories are scanned.
+    pub show_hidden: bool,
+    /// Whether `.gitignore`, `.ignore` and `.medleyignore` files are honored.
+    pub respect_ignore_files: bool,
+}
+
+impl Default for FinderConfig {
+    fn default() -> Self {
+        Self {
+            extensions: DEFAULT_EXTENSIONS
+                .iter()
+                .map(|ext| ext.to_string())
+                .collect(),
+            sniff_extensionless: true,
+            exclude: Vec::new(),
+            include: Vec::new(),
+            show_hidden: false,
+            respect_ignore_files: true,
+        }
+    }
+}
+
+/// Settings for the text area.
+#[derive(Clone, Debug)]
+pub struct EditorConfig {
+    /// Whether long lines wrap instead of running past the right edge.
+    pub soft_wrap: bool,
+    /// Column to wrap at; `None` wraps at the window width.
+    pub wrap_column: Option<usize>,
+    /// Spaces per indentation level.
+    pub indent_width: usize,
+    /// Whether Tab indents with a tab character instead of spaces.
+    pub indent_tabs: bool,
+    /// Whether the indentation of each opened file overrides the two settings above.
+    pub detect_indent: bool,
+}
+
+impl Default for EditorConfig {
+    fn default() -> Self {
+        Self {
+            soft_wrap: true,
+            wrap_column: None,
+            indent_width: 2,
+            indent_tabs: false,
+            detect_indent: true,
+        }
+    }
+}
+
+/// All editor settings.
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    pub finder: FinderConfig,
+    pub editor: EditorConfig,
+}
+
+impl Config {
+    /// Loads user configuration, then applies project overrides from `working_dir`.
+    ///
+    /// Missing files are skipped silently; malformed lines are reported on stderr
+    /// and ignored so a typo never prevents the editor from starting.
+    pub fn load(working_dir: &Path) -> Self {
+        let mut config = Self::default();
+
+        let mut sources = Vec::new();
+        if let Some(dir) = user_config_dir() {
+            sources.push(dir.join("config"));
+        }
+        sources.push(working_dir.join(PROJECT_CONFIG_FILE));
+
+        for path in sources {
+            if let Ok(content) = std::fs::read_to_string(&path) {
+                config.apply(&content, &path);
+            }
+        }
+
+        config
+    }
+
+    /// Applies `key = value` lines from a configuration file.
+    fn apply(&mut self, content: &str, source: &Path) {
+        for (idx, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                eprintln!("{}:{}: expected `key = value`", source.display(), idx + 1);
+                continue;
+            };
+            let (key, value) = (key.trim(), value.trim());
+
+            let applied = match key {
+                "finder.extensions" => {
+                    self.finder.extensions = parse_list(value)
+                        .into_iter()
+                        .map(|ext| ext.trim_start_matches('.').to_lowercase())
+                        .collect();
+                    true
+                }
+                "finder.sniff_extensionless" => {
+                    set_bool(&mut self.finder.sniff_extensionless, value)
+                }
+                "finder.exclude" => {
+                    self.finder.exclude = parse_list(value);
+                    true
+                }
+                "finder.include" => {
+                    self.finder.include = parse_list(value);
+                    true
+                }
+                "finder.hidden" => set_bool(&mut self.finder.show_hidden, value),
+                "finder.ignore_files" => set_bool(&mut self.finder.respect_ignore_files, value),
+                "editor.soft_wrap" => set_bool(&mut self.editor.soft_wrap, value),
+                // `0` or `window` wraps at the window width
+                "editor.wrap_column" => match value {
+                    "0" | "window" => {
+                        self.editor.wrap_column = None;
+                        true
+                    }
+                    _ => match value.parse() {
+                        Ok(column) => {
+                            self.editor.wrap_column = Some(column);
+                            true
+                        }
+                        Err(_) => false,
+                    },
+                },
+                "editor.indent_width" => match value.parse() {
+                    Ok(width) if (1..=8).contains(&width) => {
+                        self.editor.indent_width = width;
+                        true
+                    }
+                    _ => false,
+                },
+                "editor.indent_tabs" => set_bool(&mut self.editor.indent_tabs, value),
+                "editor.detect_indent" => set_bool(&mut self.editor.detect_indent, value),
+                _ => {
+                    eprintln!(
+                        "{}:{}: unknown setting `{}`",
+                        source.display(),
+                        idx + 1,
+                        key
+                    );
+                    continue;
+                }
+            };
+
+            if !applied {
+                eprintln!(
+                    "{}:{}: invalid value for `{}`",
+                    source.display(),
+                    idx + 1,
+                    key
+                );
+            }
+        }
+    }
+}
+
+/// Returns the directory holding the user configuration file.
+fn user_config_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("medleytext"))
+}
+
+fn parse_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Parses a boolean setting into `target`, returning false when the value is invalid.
+fn set_bool(target: &mut bool, value: &str) -> bool {
+    match value {
+        "true" | "yes" | "on" | "1" => *target = true,
+        "false" | "no" | "off" | "0" => *target = false,
+        _ => return false,
+    }
+    true
+}
@@ -0,0 +1,98 @@
+//! Multiple cursors.
+//!
+//! The editor keeps its primary caret in `cursor_position` and
+//! `selection_start`; every additional caret is a `Cursor`. Edits and motions
+//! run once per caret, from the last one in the document to the first, so the
+//! offsets of carets not yet visited stay valid and only the ones already
+//! visited have to be shifted. Carets that end up overlapping are merged.
+
+use std::ops::Range;
+
+/// A caret, with the anchor of its selection when it has one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cursor {
+    pub head: usize,
+    pub anchor: Option<usize>,
+}
+
+impl Cursor {
+    /// A caret without a selection.
+    pub fn at(head: usize) -> Self {
+        Self { head, anchor: None }
+    }
+
+    /// A caret selecting `range`, with the head at its end.
+    pub fn selecting(range: Range<usize>) -> Self {
+        Self {
+            head: range.end,
+            anchor: Some(range.start),
+        }
+    }
+
+    /// Selected range, or the empty range at the head.
+    pub fn range(&self) -> Range<usize> {
+        let anchor = self.anchor.unwrap_or(self.head);
+        anchor.min(self.head)..anchor.max(self.head)
+    }
+
+    /// Moves the caret by `delta` bytes after an edit before it.
+    pub fn shift(&mut self, delta: isize) {
+        self.head = self.head.saturating_add_signed(delta);
+        if let Some(anchor) = &mut self.anchor {
+            *anchor = anchor.saturating_add_signed(delta);
+        }
+    }
+}
+
+/// Sorts cursors by position and merges the ones that overlap.
+///
+/// Each cursor carries a flag marking the primary one; a merged cursor is
+/// primary when either part was. Adjacent selections stay separate, but a caret
+/// touching a selection is absorbed by it.
+pub fn merge(mut cursors: Vec<(Cursor, bool)>, len: usize) -> Vec<(Cursor, bool)> {
+    for (cursor, _) in &mut cursors {
+        cursor.head = cursor.head.min(len);
+        cursor.anchor = cursor.anchor.map(|anchor| anchor.min(len));
+    }
+    cursors.sort_by_key(|(cursor, _)| (cursor.range().start, cursor.range().end));
+
+    let mut merged: Vec<(Cursor, bool)> = Vec::with_capacity(cursors.len());
+    for (cursor, primary) in cursors {
+        let next = cursor.range();
+        if let Some((last, last_primary)) = merged.last_mut() {
+            let prev = last.range();
+            let touching = next.start == prev.end && (prev.is_empty() || next.is_empty());
+            if next.start < prev.end || touching {
+                let union = prev.start..prev.end.max(next.end);
+                *last = if union.is_empty() {
+                    Cursor::at(union.start)
+                } else {
+                    Cursor::selecting(union)
+                };
+                *last_primary |= primary;
+                continue;
+            }
+        }
+        merged.push((cursor, primary));
+    }
+    merged
+}
+
+/// Next occurrence of `needle` at or after `from`, wrapping around to the start,
+/// that is not one of the `taken` ranges.
+pub fn next_occurrence(
+    content: &str,
+    needle: &str,
+    from: usize,
+    taken: &[Range<usize>],
+) -> Option<Range<usize>> {
+    if needle.is_empty() {
+        return None;
+    }
+    content[from..]
+        .match_indices(needle)
+        .map(|(idx, _)| from + idx)
+        .chain(content[..from].match_indices(needle).map(|(idx, _)| idx))
+        .map(|start| start..start + needle.len())
+        .find(|range| !taken.contains(range))
+}
@@ -4,15 +4,36 @@
 //! functionality including cursor management, text selection, clipboard operations,
 //! scrolling, and rendering with real-time markdown syntax highlighting.
 
+use std::cell::RefCell;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::rc::Rc;
+
 use gpui::{
-    App, ClipboardItem, Context, FocusHandle, Focusable, KeyDownEvent, MouseDownEvent, Render,
-    Rgba, ScrollWheelEvent, Window, actions, div, prelude::*, px, rgb,
+    App, Bounds, ClickEvent, ClipboardItem, Context, FocusHandle, Focusable, KeyDownEvent,
+    MouseDownEvent, MouseMoveEvent, MouseUpEvent, Pixels, Point, Render, Rgba, ScrollWheelEvent,
+    Task, Window, actions, canvas, div, prelude::*, px, rgb,
 };
 
 use crate::autocomplete::Autocomplete;
-use crate::find::{ActiveInput, FindPanelState, SearchMatch};
-use crate::markdown::MarkdownHighlighter;
-use crate::palette::Palette;
+use crate::column::{self, BlockSelection, Position};
+use crate::config::Config;
+use crate::cursors::{self, Cursor};
+use crate::file_ops::{FileOperation, relocate};
+use crate::file_tree::{FILE_TREE_WIDTH, FileTreeState, TreeAction, TreeOutcome};
+use crate::find::{ActiveInput, FindPanelState, SearchHistory, SearchMatch};
+use crate::folding::{self, FOLD_GUTTER_WIDTH, FoldRegion, FoldState};
+use crate::frecency::OpenHistory;
+use crate::indent::{self, Indent};
+use crate::lines::{self, LineEdit};
+use crate::lists::{self, Continuation, ListItem};
+use crate::markdown::{Heading, MarkdownHighlighter};
+use crate::outline::{self, DraggedSection, OUTLINE_WIDTH, OutlineEdit};
+use crate::palette::{Palette, PaletteContext};
+use crate::selection::{self, SelectionUnit};
+use crate::text_input::{InputCommand, InputResponse, TextInput};
+use crate::text_layout::{EDITOR_FONT_FAMILY, EDITOR_FONT_SIZE, TextMetrics};
+use crate::workspace::WorkspaceIndex;
+use crate::wrap::{self, VisualRow};
 
 // Define GPUI actions for keyboard shortcuts and user commands.
 // These actions are bound to keys in main.rs and handled by the TextEditor.
@@ -39,6 +60,62 @@ actions!(
         FindNext,
         FindPrevious,
         TogglePalette,
+        ToggleCommandPalette,
+        GoToSymbol,
+        ToggleOutline,
+        MoveSectionUp,
+        MoveSectionDown,
+        PromoteSection,
+        DemoteSection,
+        ToggleFileTree,
+        Fold,
+        Unfold,
+        FoldAll,
+        UnfoldAll,
+        MoveHome,
+        MoveEnd,
+        ToggleSoftWrap,
+        MoveWordLeft,
+        MoveWordRight,
+        SelectWordLeft,
+        SelectWordRight,
+        SelectHome,
+        SelectEnd,
+        MoveToStart,
+        MoveToEnd,
+        SelectToStart,
+        SelectToEnd,
+        PageUp,
+        PageDown,
+        SelectPageUp,
+        SelectPageDown,
+        MoveParagraphUp,
+        MoveParagraphDown,
+        SelectParagraphUp,
+        SelectParagraphDown,
+        Delete,
+        DeleteWordBackward,
+        DeleteWordForward,
+        DeleteLine,
+        DeleteToLineEnd,
+        AddNextOccurrence,
+        AddCursorAbove,
+        AddCursorBelow,
+        SelectAllMatches,
+        ColumnSelectUp,
+        ColumnSelectDown,
+        ColumnSelectLeft,
+        ColumnSelectRight,
+        IndentLines,
+        OutdentLines,
+        MoveLineUp,
+        MoveLineDown,
+        DuplicateLine,
+        JoinLines,
+        SortLines,
+        ReverseLines,
+        UniqueLines,
+        TransposeChars,
     ]
 );
 
@@ -58,7 +135,6 @@ actions!(
 ///
 /// - Replace `String` with rope data structure for better performance on large files
 /// - Add undo/redo stack
-/// - Implement multi-cursor support
 /// - Add line numbers in gutter
 /// - Consider caching tokenized lines for better rendering performance
 pub struct TextEditor {
@@ -71,6 +147,15 @@ pub struct TextEditor {
     /// Anchor point for text selection. When `Some`, a selection exists between this and `cursor_position`.
     selection_start: Option<usize>,
 
+    /// Carets besides the primary one, for editing in several places at once.
+    extra_cursors: Vec<Cursor>,
+
+    /// Rectangle last selected with Alt+Shift, while the carets still form it.
+    block_selection: Option<BlockSelection>,
+
+    /// Text of the last block copied, so pasting it inserts a block again.
+    block_clipboard: Option<String>,
+
     /// GPUI focus handle for keyboard event routing.
     focus_handle: FocusHandle,
 
@@ -83,8 +168,11 @@ pub struct TextEditor {
     /// Command palette for fuzzy file finding. `None` when closed.
     palette: Option<gpui::Entity<Palette>>,
 
-    /// Working directory for file operations and palette scanning.
-    working_dir: std::path::PathBuf,
+    /// Background index of workspace documents, shared with the palette.
+    workspace: gpui::Entity<WorkspaceIndex>,
+
+    /// Open counts and times for files in this workspace, used to rank palette results.
+    open_history: OpenHistory,
 
     /// Tracks if buffer has unsaved changes.
     is_dirty: bool,
@@ -95,8 +183,133 @@ pub struct TextEditor {
     /// Find/replace panel state. `None` when closed.
     find_panel: Option<FindPanelState>,
 
+    /// Previously used search queries and replacements, persisted across sessions.
+    find_history: SearchHistory,
+
     /// Guards against the editor handling Enter after the find panel consumed it.
     suppress_next_enter: bool,
+
+    /// Whether the outline sidebar with the document's headings is shown.
+    show_outline: bool,
+
+    /// Whether long lines wrap at `wrap_column` or the window edge.
+    soft_wrap: bool,
+
+    /// Configured wrap column; `None` wraps at the window width.
+    wrap_column: Option<usize>,
+
+    /// Columns lines are currently wrapped at, updated from the window size on
+    /// every render. `None` when soft wrap is off.
+    wrap_width: Option<usize>,
+
+    /// Indentation from the configuration, used when a file has none to detect.
+    configured_indent: Indent,
+
+    /// Whether each opened file's own indentation replaces `configured_indent`.
+    detect_indent: bool,
+
+    /// Indentation Tab inserts in the current file.
+    indent: Indent,
+
+    /// Folded headings, list items and code blocks of the current file.
+    folds: FoldState,
+
+    /// Fold regions and screen rows from the last time they were needed.
+    fold_layout: RefCell<Option<Rc<FoldLayout>>>,
+
+    /// Headings from the last time they were needed.
+    heading_cache: RefCell<Option<HeadingCache>>,
+
+    /// Metrics of the document font, measured on every render.
+    text_metrics: TextMetrics,
+
+    /// Window-space bounds of the text viewport from the last layout.
+    text_bounds: Bounds<Pixels>,
+
+    /// Selection being made with the mouse. `None` when no button is held.
+    mouse_drag: Option<MouseDrag>,
+
+    /// File tree sidebar for browsing the working directory.
+    file_tree: FileTreeState,
+
+    /// Repaints the file tree when the workspace index changes.
+    _workspace_subscription: gpui::Subscription,
+}
+
+/// Fold regions and screen rows of one state of the document.
+///
+/// Finding the regions parses every heading, so a layout is reused until the
+/// content, the folds or the wrap width change.
+struct FoldLayout {
+    /// Hash of the content it was computed for
+    content_hash: u64,
+    folds: FoldState,
+    wrap_width: Option<usize>,
+    /// Every foldable region
+    regions: Vec<FoldRegion>,
+    /// Regions that are currently folded
+    folded: Vec<FoldRegion>,
+    /// Document lines not hidden by a fold
+    visible: Vec<usize>,
+    rows: Rc<[VisualRow]>,
+}
+
+/// Headings of one state of the document, reused until the content changes,
+/// so the outline and the section commands do not parse on every use.
+struct HeadingCache {
+    /// Hash of the content they were parsed from
+    content_hash: u64,
+    headings: Rc<[Heading]>,
+}
+
+/// How often a drag past the top or bottom of the text scrolls.
+const AUTOSCROLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(16);
+
+/// A selection being made by dragging with the left button held.
+struct MouseDrag {
+    /// Whether the drag extends by characters, words or lines
+    unit: SelectionUnit,
+    /// Range selected by the initial click, kept selected while dragging
+    origin: std::ops::Range<usize>,
+    /// Whether the drag selects a block (Alt+Shift) instead of a range
+    column: bool,
+    /// Last pointer position, in window coordinates
+    pointer: Point<Pixels>,
+    /// Scrolls while the pointer is held beyond the text; dropped with the drag
+    _autoscroll: Task<()>,
+}
+
+/// Caret motions shared by the move and select actions.
+#[derive(Clone, Copy)]
+enum Motion {
+    WordLeft,
+    WordRight,
+    Home,
+    End,
+    DocumentStart,
+    DocumentEnd,
+    PageUp,
+    PageDown,
+    ParagraphUp,
+    ParagraphDown,
+}
+
+impl Motion {
+    /// Equivalent command for the find panel's inputs, if the motion applies there.
+    fn input_command(self, select: bool) -> Option<InputCommand> {
+        let command = match (self, select) {
+            (Motion::WordLeft, false) => InputCommand::MoveWordLeft,
+            (Motion::WordLeft, true) => InputCommand::SelectWordLeft,
+            (Motion::WordRight, false) => InputCommand::MoveWordRight,
+            (Motion::WordRight, true) => InputCommand::SelectWordRight,
+            (Motion::Home | Motion::DocumentStart, false) => InputCommand::MoveHome,
+            (Motion::Home | Motion::DocumentStart, true) => InputCommand::SelectHome,
+            (Motion::End | Motion::DocumentEnd, false) => InputCommand::MoveEnd,
+            (Motion::End | Motion::DocumentEnd, true) => InputCommand::SelectEnd,
+            _ => return None,
+        };
+        Some(command)
+    }
 }
 
 #[derive(Clone)]
@@ -198,20 +411,63 @@ impl TextEditor {
         };
 
         let working_dir = std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."));
+        let working_dir = working_dir.canonicalize().unwrap_or(working_dir);
+        let workspace = cx.new(|cx| WorkspaceIndex::new(working_dir.clone(), cx));
+        let workspace_subscription = cx.observe(&workspace, |editor, _, cx| {
+            if editor.file_tree.visible {
+                cx.notify();
+            }
+        });
+
+        let mut open_history = OpenHistory::load(&working_dir);
+        if let Some(path) = &current_file {
+            open_history.record_open(std::path::Path::new(path));
+        }
+        let folds = FoldState::load(current_file.as_deref().map(std::path::Path::new));
+        let config = Config::load(&working_dir);
+        let configured_indent = Indent {
+            tabs: config.editor.indent_tabs,
+            width: config.editor.indent_width,
+        };
+        let indent = if config.editor.detect_indent {
+            Indent::detect(&content).unwrap_or(configured_indent)
+        } else {
+            configured_indent
+        };
 
         Self {
             content,
             cursor_position: 0,
             selection_start: None,
+            extra_cursors: Vec::new(),
+            block_selection: None,
+            block_clipboard: None,
             focus_handle: cx.focus_handle(),
             current_file,
             scroll_offset: 0.0,
             palette: None,
-            working_dir,
+            workspace,
+            open_history,
             is_dirty: false,
             autocomplete: None,
             find_panel: None,
+            find_history: SearchHistory::load(),
             suppress_next_enter: false,
+            show_outline: false,
+            soft_wrap: config.editor.soft_wrap,
+            wrap_column: config.editor.wrap_column,
+            wrap_width: None,
+            configured_indent,
+            detect_indent: config.editor.detect_indent,
+            indent,
+            folds,
+            fold_layout: RefCell::new(None),
+            heading_cache: RefCell::new(None),
+            text_metrics: TextMetrics::default(),
+            text_bounds: Bounds::default(),
+            mouse_drag: None,
+            file_tree: FileTreeState::new(working_dir, cx.focus_handle()),
+            _workspace_subscription: workspace_subscription,
         }
     }
 
@@ -257,11 +513,304 @@ impl TextEditor {
     ///
     /// Returns `None` if no selection is active.
     /// Used for copy and cut operations.
+    ///
+    /// With several cursors, the selections are joined with line breaks in
+    /// document order.
     fn get_selected_text(&self) -> Option<String> {
+        if !self.extra_cursors.is_empty() {
+            let texts: Vec<&str> = cursors::merge(self.cursors(), self.content.len())
+                .iter()
+                .map(|(cursor, _)| &self.content[cursor.range()])
+                .filter(|text| !text.is_empty())
+                .collect();
+            return (!texts.is_empty()).then(|| texts.join("\n"));
+        }
         self.get_selection_range()
             .map(|(start, end)| self.content[start..end].to_string())
     }
 
+    /// Every caret, the primary one included, flagged `true` for the primary.
+    fn cursors(&self) -> Vec<(Cursor, bool)> {
+        let mut cursors: Vec<(Cursor, bool)> = self
+            .extra_cursors
+            .iter()
+            .map(|cursor| (*cursor, false))
+            .collect();
+        cursors.push((
+            Cursor {
+                head: self.cursor_position,
+                anchor: self.selection_start,
+            },
+            true,
+        ));
+        cursors
+    }
+
+    /// Replaces every caret, merging overlapping ones; the flagged one becomes primary.
+    fn set_cursors(&mut self, cursors: Vec<(Cursor, bool)>) {
+        self.extra_cursors.clear();
+        for (cursor, primary) in cursors::merge(cursors, self.content.len()) {
+            if primary {
+                self.cursor_position = cursor.head;
+                self.selection_start = cursor.anchor;
+            } else {
+                self.extra_cursors.push(cursor);
+            }
+        }
+    }
+
+    /// Runs `edit` once per caret, as if that caret were the only one.
+    ///
+    /// `edit` works on `cursor_position` and `selection_start` and gets the
+    /// caret's index in document order. Carets are visited from the last to the
+    /// first, and the ones already visited are shifted by any change in length.
+    fn for_each_cursor(&mut self, mut edit: impl FnMut(&mut Self, usize)) {
+        if self.extra_cursors.is_empty() {
+            edit(self, 0);
+            return;
+        }
+
+        let cursors = cursors::merge(self.cursors(), self.content.len());
+        let mut visited: Vec<(Cursor, bool)> = Vec::with_capacity(cursors.len());
+        for (index, (cursor, primary)) in cursors.into_iter().enumerate().rev() {
+            self.cursor_position = cursor.head;
+            self.selection_start = cursor.anchor;
+            let before = self.content.len();
+            edit(self, index);
+            let delta = self.content.len() as isize - before as isize;
+            for (later, _) in &mut visited {
+                later.shift(delta);
+            }
+            visited.push((
+                Cursor {
+                    head: self.cursor_position,
+                    anchor: self.selection_start,
+                },
+                primary,
+            ));
+        }
+        self.set_cursors(visited);
+    }
+
+    /// Drops every caret but the primary one.
+    fn clear_extra_cursors(&mut self) {
+        self.extra_cursors.clear();
+    }
+
+    /// Handles Ctrl+D: selects the word under the caret, or adds a cursor
+    /// selecting the next occurrence of the selected text.
+    fn handle_add_next_occurrence(
+        &mut self,
+        _: &AddNextOccurrence,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.autocomplete = None;
+        let Some((start, end)) = self
+            .get_selection_range()
+            .filter(|(start, end)| start < end)
+        else {
+            let word = selection::word_range(&self.content, self.cursor_position);
+            if !word.is_empty() {
+                self.selection_start = Some(word.start);
+                self.cursor_position = word.end;
+                cx.notify();
+            }
+            return;
+        };
+
+        let needle = self.content[start..end].to_string();
+        let taken: Vec<std::ops::Range<usize>> = self
+            .cursors()
+            .iter()
+            .map(|(cursor, _)| cursor.range())
+            .collect();
+        let from = taken.iter().map(|range| range.end).max().unwrap_or(end);
+        let Some(found) = cursors::next_occurrence(&self.content, &needle, from, &taken) else {
+            return;
+        };
+
+        // The new occurrence becomes the primary cursor
+        let mut cursors: Vec<(Cursor, bool)> = self
+            .cursors()
+            .into_iter()
+            .map(|(cursor, _)| (cursor, false))
+            .collect();
+        cursors.push((Cursor::selecting(found), true));
+        self.set_cursors(cursors);
+        self.ensure_position_visible(self.cursor_position);
+        cx.notify();
+    }
+
+    /// Handles Ctrl+Alt+Up: adds a cursor on the row above the topmost one.
+    fn handle_add_cursor_above(
+        &mut self,
+        _: &AddCursorAbove,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.add_cursor_vertically(-1, cx);
+    }
+
+    /// Handles Ctrl+Alt+Down: adds a cursor on the row below the bottommost one.
+    fn handle_add_cursor_below(
+        &mut self,
+        _: &AddCursorBelow,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.add_cursor_vertically(1, cx);
+    }
+
+    /// Adds a primary cursor one row past the outermost cursor in `direction`,
+    /// at the same column.
+    fn add_cursor_vertically(&mut self, direction: isize, cx: &mut Context<Self>) {
+        self.autocomplete = None;
+        let cursors: Vec<(Cursor, bool)> = self
+            .cursors()
+            .into_iter()
+            .map(|(cursor, _)| (cursor, false))
+            .collect();
+        let heads = cursors.iter().map(|(cursor, _)| cursor.head);
+        let edge = if direction < 0 {
+            heads.min()
+        } else {
+            heads.max()
+        }
+        .unwrap_or(self.cursor_position);
+
+        let target = self.offset_rows_away(edge, direction);
+        if target == edge {
+            return;
+        }
+        let mut cursors = cursors;
+        cursors.push((Cursor::at(target), true));
+        self.set_cursors(cursors);
+        self.ensure_position_visible(self.cursor_position);
+        cx.notify();
+    }
+
+    /// Handles Alt+Shift+Up: extends the block selection up a line, starting
+    /// one at the caret if there is none.
+    fn handle_column_select_up(
+        &mut self,
+        _: &ColumnSelectUp,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.extend_block(-1, 0, cx);
+    }
+
+    /// Handles Alt+Shift+Down: extends the block selection down a line.
+    fn handle_column_select_down(
+        &mut self,
+        _: &ColumnSelectDown,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.extend_block(1, 0, cx);
+    }
+
+    /// Handles Alt+Shift+Left: moves the block selection's moving edge a
+    /// column left, starting a block at the caret if there is none.
+    fn handle_column_select_left(
+        &mut self,
+        _: &ColumnSelectLeft,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.extend_block(0, -1, cx);
+    }
+
+    /// Handles Alt+Shift+Right: moves the block selection's moving edge a column right.
+    fn handle_column_select_right(
+        &mut self,
+        _: &ColumnSelectRight,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.extend_block(0, 1, cx);
+    }
+
+    /// The block selection, if the carets have not changed since it was made.
+    fn active_block(&self) -> Option<BlockSelection> {
+        let block = self.block_selection?;
+        let expected = cursors::merge(block.cursors(&self.content), self.content.len());
+        let current = cursors::merge(self.cursors(), self.content.len());
+        (expected == current).then_some(block)
+    }
+
+    /// Selects the rectangle of `block` with one cursor per line.
+    fn select_block(&mut self, block: BlockSelection) {
+        self.block_selection = Some(block);
+        self.set_cursors(block.cursors(&self.content));
+    }
+
+    /// Moves the corner of the block selection by `lines` and `columns`.
+    fn extend_block(&mut self, lines: isize, columns: isize, cx: &mut Context<Self>) {
+        self.autocomplete = None;
+        let block = self
+            .active_block()
+            .unwrap_or_else(|| BlockSelection::at(&self.content, self.cursor_position));
+        self.select_block(block.moved(&self.content, lines, columns));
+        self.ensure_position_visible(self.cursor_position);
+        cx.notify();
+    }
+
+    /// Handles Ctrl+Shift+L (or Alt+Enter in the find panel): selects every
+    /// match of the find panel's query with its own cursor and closes the panel.
+    /// Without the panel, selects every occurrence of the selection, or of the
+    /// word under the caret.
+    fn handle_select_all_matches(
+        &mut self,
+        _: &SelectAllMatches,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.autocomplete = None;
+        let (ranges, primary) = if let Some(panel) = &self.find_panel {
+            let ranges: Vec<std::ops::Range<usize>> = panel
+                .matches
+                .iter()
+                .map(|search_match| search_match.start..search_match.end)
+                .collect();
+            (ranges, panel.current_index().unwrap_or(0))
+        } else {
+            let range = match self.get_selection_range() {
+                Some((start, end)) if start < end => start..end,
+                _ => selection::word_range(&self.content, self.cursor_position),
+            };
+            let needle = &self.content[range.clone()];
+            let ranges: Vec<std::ops::Range<usize>> = self
+                .content
+                .match_indices(needle)
+                .map(|(idx, _)| idx..idx + needle.len())
+                .collect();
+            let primary = ranges.iter().position(|found| *found == range).unwrap_or(0);
+            (ranges, primary)
+        };
+        if ranges.iter().all(|range| range.is_empty()) {
+            return;
+        }
+
+        self.close_find_panel();
+        let cursors = ranges
+            .into_iter()
+            .enumerate()
+            .map(|(idx, range)| (Cursor::selecting(range), idx == primary))
+            .collect();
+        self.set_cursors(cursors);
+        self.ensure_position_visible(self.cursor_position);
+        cx.notify();
+    }
+
+    /// Brings the view up to date after an edit: search matches are found
+    /// again and a fold the caret ended up in is opened.
+    fn content_changed(&mut self) {
+        self.refresh_search_matches();
+        self.reveal_offset(self.cursor_position);
+    }
+
     /// Recomputes matches when content or query changes.
     fn refresh_search_matches(&mut self) {
         let has_panel = self.find_panel.is_some();
@@ -283,45 +832,248 @@ impl TextEditor {
         let mut panel = FindPanelState::new(initial);
         panel.recompute_matches(&self.content);
         self.find_panel = Some(panel);
+        self.clear_extra_cursors();
     }
 
-    /// Closes the panel and clears highlights.
+    /// Closes the panel and clears highlights, remembering the query for next time.
     fn close_find_panel(&mut self) {
-        self.find_panel = None;
+        if let Some(panel) = self.find_panel.take() {
+            self.find_history.record(&panel);
+        }
+    }
+
+    /// Handles Up/Down while the find panel is open by browsing history for the active row.
+    ///
+    /// Returns false only when the panel is closed, so the key moves the caret;
+    /// while it is open the key is consumed even when there is nothing to recall.
+    fn handle_find_history(&mut self, direction: isize, cx: &mut Context<Self>) -> bool {
+        let Some(panel) = self.find_panel.as_mut() else {
+            return false;
+        };
+
+        let entries = self.find_history.entries(panel.active_input);
+        if !panel.recall_history(entries, direction, &self.content) {
+            return true;
+        }
+        if panel.has_matches() {
+            panel.refresh_anchor();
+            self.focus_current_search_match();
+        } else {
+            self.selection_start = None;
+        }
+        cx.notify();
+        true
     }
 
     /// Ensures the byte offset is visible inside the viewport.
     fn ensure_position_visible(&mut self, byte_offset: usize) {
-        let line_height = 22.0;
-        let viewport_height = 538.0;
-        let mut consumed = 0;
+        let line_height = f32::from(self.text_metrics.line_height);
+        let viewport_height = f32::from(self.text_bounds.size.height);
 
-        for (idx, line) in self.content.split('\n').enumerate() {
-            let line_len = line.len();
-            if byte_offset <= consumed + line_len {
-                let top = idx as f32 * line_height;
-                let bottom = top + line_height;
-                let viewport_top = self.scroll_offset;
-                let viewport_bottom = viewport_top + viewport_height;
-
-                if top < viewport_top {
-                    self.scroll_offset = top.max(0.0);
-                } else if bottom > viewport_bottom {
-                    self.scroll_offset = (bottom - viewport_height).max(0.0);
-                }
-                break;
+        // A position inside a fold can only be shown by opening it
+        self.reveal_offset(byte_offset);
+        if viewport_height <= 0.0 {
+            // Not laid out yet, so there is no viewport to scroll
+            return;
+        }
+
+        let top = self.visual_row(byte_offset) as f32 * line_height;
+        let bottom = top + line_height;
+        let viewport_top = self.scroll_offset;
+        let viewport_bottom = viewport_top + viewport_height;
+
+        if top < viewport_top {
+            self.scroll_offset = top.max(0.0);
+        } else if bottom > viewport_bottom {
+            self.scroll_offset = (bottom - viewport_height).max(0.0);
+        }
+    }
+
+    /// Byte offset where each line starts.
+    fn line_starts(&self) -> Vec<usize> {
+        std::iter::once(0)
+            .chain(self.content.match_indices('\n').map(|(idx, _)| idx + 1))
+            .collect()
+    }
+
+    /// Zero-based line containing `offset`, given the result of `line_starts`.
+    fn line_at(starts: &[usize], offset: usize) -> usize {
+        starts
+            .partition_point(|&start| start <= offset)
+            .saturating_sub(1)
+    }
+
+    /// Byte offset of the end of `line`, before its line break.
+    fn line_end(&self, starts: &[usize], line: usize) -> usize {
+        starts
+            .get(line + 1)
+            .map(|next| next - 1)
+            .unwrap_or(self.content.len())
+    }
+
+    fn content_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.content.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Headings of the document, parsed again only when the content changed
+    /// since the last call.
+    fn headings(&self) -> Rc<[Heading]> {
+        let content_hash = self.content_hash();
+        if let Some(cache) = &*self.heading_cache.borrow()
+            && cache.content_hash == content_hash
+        {
+            return cache.headings.clone();
+        }
+
+        let headings: Rc<[Heading]> = MarkdownHighlighter::headings(&self.content).into();
+        *self.heading_cache.borrow_mut() = Some(HeadingCache {
+            content_hash,
+            headings: headings.clone(),
+        });
+        headings
+    }
+
+    /// Fold regions and screen rows of the document, computed again only when
+    /// the content, the folds or the wrap width changed since the last call.
+    fn fold_layout(&self) -> Rc<FoldLayout> {
+        let content_hash = self.content_hash();
+        let cached = self.fold_layout.borrow().clone().filter(|layout| {
+            layout.content_hash == content_hash
+                && layout.folds == self.folds
+                && layout.wrap_width == self.wrap_width
+        });
+        if let Some(layout) = cached {
+            return layout;
+        }
+
+        let regions = folding::fold_regions(&self.content);
+        let folded = self.folds.resolve(&self.content, &regions);
+        let visible = folding::visible_lines(self.content.split('\n').count(), &folded);
+        let rows = wrap::visual_rows(&self.content, &visible, self.wrap_width).into();
+        let layout = Rc::new(FoldLayout {
+            content_hash,
+            folds: self.folds.clone(),
+            wrap_width: self.wrap_width,
+            regions,
+            folded,
+            visible,
+            rows,
+        });
+        *self.fold_layout.borrow_mut() = Some(layout.clone());
+        layout
+    }
+
+    /// Regions of the document that are currently folded.
+    fn folded_regions(&self) -> Vec<FoldRegion> {
+        self.fold_layout().folded.clone()
+    }
+
+    /// Screen rows of the document: visible lines, wrapped at `wrap_width`.
+    fn visual_rows(&self) -> Rc<[VisualRow]> {
+        self.fold_layout().rows.clone()
+    }
+
+    /// Screen row showing `offset`; hidden lines map to their fold's header.
+    fn visual_row(&self, offset: usize) -> usize {
+        wrap::row_at(&self.visual_rows(), offset)
+    }
+
+    /// Offset at `column` of a screen row, counting its hanging indent.
+    ///
+    /// Rows that continue on the next row stop before their last character, so
+    /// the caret stays on the row instead of jumping to the wrap point.
+    fn offset_in_row(&self, row: &VisualRow, column: usize) -> usize {
+        let text = &self.content[row.start..row.end];
+        let end = if row.last {
+            text.len()
+        } else {
+            text.char_indices().next_back().map_or(0, |(idx, _)| idx)
+        };
+        let target = column.saturating_sub(row.indent);
+        let mut width = 0;
+        for (idx, ch) in text[..end].char_indices() {
+            width += wrap::char_width(ch);
+            // Stop before a character that would end past the column
+            if width > target {
+                return row.start + idx;
             }
-            consumed += line_len + 1;
+        }
+        row.start + end
+    }
+
+    /// Unfolds every folded region that hides the line containing `offset`.
+    fn reveal_offset(&mut self, offset: usize) {
+        if self.folds.is_empty() {
+            return;
+        }
+        let folded = self.folded_regions();
+        let line = self.content[..offset].matches('\n').count();
+        for region in folded.iter().filter(|region| region.hides(line)) {
+            self.folds.unfold(&self.content, region);
+        }
+    }
+
+    /// Moves an `offset` that landed inside a fold out of it.
+    ///
+    /// Moving forward continues after the fold; moving backward stops at the
+    /// end of the fold's header line, where the caret is drawn.
+    fn skip_folded(&self, offset: usize, forward: bool) -> usize {
+        let folded = self.folded_regions();
+        let starts = self.line_starts();
+        let line = Self::line_at(&starts, offset);
+        let Some(outer) = folded
+            .iter()
+            .filter(|region| region.hides(line))
+            .min_by_key(|region| region.start)
+        else {
+            return offset;
+        };
+
+        let header_end = self.line_end(&starts, outer.start);
+        if forward {
+            starts.get(outer.end + 1).copied().unwrap_or(header_end)
+        } else {
+            header_end
         }
     }
 
     /// Applies selection and caret to the provided match range.
     fn focus_match(&mut self, range: SearchMatch) {
+        self.clear_extra_cursors();
         self.selection_start = Some(range.start);
         self.cursor_position = range.end;
         self.ensure_position_visible(range.start);
     }
 
+    /// Moves the caret to `offset` without a selection and scrolls it into view.
+    fn jump_to(&mut self, offset: usize, cx: &mut Context<Self>) {
+        self.clear_extra_cursors();
+        self.cursor_position = offset.min(self.content.len());
+        self.selection_start = None;
+        self.ensure_position_visible(self.cursor_position);
+        cx.notify();
+    }
+
+    /// Opens `path` (unless it is already the current file) and jumps to the start of `line`.
+    fn open_at_line(&mut self, path: std::path::PathBuf, line: usize, cx: &mut Context<Self>) {
+        let is_current = self.current_file.as_ref().is_some_and(|current| {
+            std::path::Path::new(current).canonicalize().ok().as_ref() == Some(&path)
+        });
+        if !is_current && !self.load_file(path, cx) {
+            return;
+        }
+
+        let offset = self
+            .content
+            .split('\n')
+            .take(line)
+            .map(|text| text.len() + 1)
+            .sum();
+        self.jump_to(offset, cx);
+    }
+
     fn focus_current_search_match(&mut self) -> bool {
         if let Some(panel) = &self.find_panel {
             if let Some(range) = panel.current_match() {
@@ -346,20 +1098,24 @@ impl TextEditor {
         }
     }
 
-    /// Handles backspace when the find panel is active.
-    fn handle_find_backspace(&mut self, cx: &mut Context<Self>) -> bool {
-        if let Some(panel) = self.find_panel.as_mut() {
-            panel.backspace(&self.content);
+    /// Routes an editing command to the find panel's active input when the panel is open.
+    fn handle_find_input(&mut self, command: InputCommand, cx: &mut Context<Self>) -> bool {
+        let Some(panel) = self.find_panel.as_mut() else {
+            return false;
+        };
+
+        if panel.apply_input(command, &self.content, cx) == InputResponse::Edited
+            && panel.active_input == ActiveInput::Query
+        {
             if panel.has_matches() {
                 panel.refresh_anchor();
                 self.focus_current_search_match();
             } else {
                 self.selection_start = None;
             }
-            cx.notify();
-            return true;
         }
-        false
+        cx.notify();
+        true
     }
 
     /// Replaces the current match with the replacement text.
@@ -373,18 +1129,22 @@ impl TextEditor {
             if !panel.show_replace {
                 return false;
             }
-            let replace_value = panel.replace.clone();
+            let replace_value = panel.replace.text().to_string();
             let range = panel.current_match().unwrap();
             (range, replace_value)
         };
 
+        if let Some(panel) = self.find_panel.as_ref() {
+            self.find_history.record(panel);
+        }
+
         self.content
             .replace_range(range.start..range.end, &replacement);
         self.cursor_position = range.start + replacement.len();
         self.selection_start = Some(range.start);
         self.is_dirty = true;
 
-        self.refresh_search_matches();
+        self.content_changed();
         if let Some(panel) = self.find_panel.as_mut() {
             panel.refresh_anchor();
         }
@@ -398,13 +1158,20 @@ impl TextEditor {
                 Some(panel) if panel.has_query() && panel.show_replace => panel,
                 _ => return 0,
             };
-            (panel.query.clone(), panel.replace.clone())
+            (
+                panel.query.text().to_string(),
+                panel.replace.text().to_string(),
+            )
         };
 
         if needle.is_empty() {
             return 0;
         }
 
+        if let Some(panel) = self.find_panel.as_ref() {
+            self.find_history.record(panel);
+        }
+
         let mut replaced = 0;
         let mut search_index = 0;
 
@@ -425,7 +1192,7 @@ impl TextEditor {
             self.cursor_position = self.cursor_position.min(self.content.len());
             self.selection_start = None;
             self.is_dirty = true;
-            self.refresh_search_matches();
+            self.content_changed();
             if let Some(panel) = self.find_panel.as_mut() {
                 panel.refresh_anchor();
             }
@@ -439,8 +1206,8 @@ impl TextEditor {
         text: &str,
         token_color: Rgba,
         token_start: usize,
-        selection_range: Option<(usize, usize)>,
-        cursor_position: Option<usize>,
+        selections: &[(usize, usize)],
+        cursors: &[usize],
         search_panel: Option<&FindPanelState>,
     ) -> Vec<SegmentPiece> {
         let token_len = text.len();
@@ -451,7 +1218,7 @@ impl TextEditor {
         let token_end = token_start + token_len;
         let mut slices = Vec::new();
 
-        if let Some((sel_start, sel_end)) = selection_range {
+        for &(sel_start, sel_end) in selections {
             if sel_end > token_start && sel_start < token_end {
                 slices.push(HighlightSlice {
                     start: sel_start.max(token_start) - token_start,
@@ -527,14 +1294,15 @@ impl TextEditor {
             }));
         }
 
-        if let Some(cursor_abs) = cursor_position {
-            let overlaps_selection = selection_range
-                .map(|(sel_start, sel_end)| sel_end > token_start && sel_start < token_end)
-                .unwrap_or(false);
+        // Carets of a selection are hidden behind its highlight
+        for &cursor_abs in cursors {
+            let in_selection = selections
+                .iter()
+                .any(|&(sel_start, sel_end)| sel_start <= cursor_abs && cursor_abs <= sel_end);
 
-            if !overlaps_selection && cursor_abs >= token_start && cursor_abs < token_end {
+            if !in_selection && cursor_abs >= token_start && cursor_abs < token_end {
                 let cursor_offset = cursor_abs - token_start;
-                return Self::insert_cursor_segment(segments, cursor_offset);
+                segments = Self::insert_cursor_segment(segments, cursor_offset);
             }
         }
 
@@ -613,21 +1381,6 @@ impl TextEditor {
             return true;
         }
 
-        // Tab cycles between query/replace when both are visible.
-        if event.keystroke.key == "tab" {
-            if let Some(panel) = self.find_panel.as_mut() {
-                if panel.show_replace {
-                    let next = match panel.active_input {
-                        ActiveInput::Query => ActiveInput::Replace,
-                        ActiveInput::Replace => ActiveInput::Query,
-                    };
-                    panel.set_active_input(next);
-                    cx.notify();
-                    return true;
-                }
-            }
-        }
-
         // Ctrl+H toggles replace visibility.
         if event.keystroke.key == "h"
             && event.keystroke.modifiers.control
@@ -671,25 +1424,12 @@ impl TextEditor {
             return true;
         }
 
-        // Regular character input updates the active field.
-        if let Some(ref key_char) = event.keystroke.key_char {
-            if key_char.len() == 1
-                && !event.keystroke.modifiers.control
-                && !event.keystroke.modifiers.alt
-                && !event.keystroke.modifiers.platform
-            {
-                if let Some(c) = key_char.chars().next() {
-                    if let Some(panel) = self.find_panel.as_mut() {
-                        panel.push_char(c, &self.content);
-                        if panel.has_matches() {
-                            panel.refresh_anchor();
-                            self.focus_current_search_match();
-                        }
-                    }
-                    cx.notify();
-                    return true;
-                }
-            }
+        // Text input and caret keys edit the active field. Stopping propagation keeps
+        // keys that also have editor bindings (arrows, Ctrl+V, ...) out of the buffer.
+        if let Some(command) = TextInput::command_for_keystroke(&event.keystroke) {
+            self.handle_find_input(command, cx);
+            cx.stop_propagation();
+            return true;
         }
 
         false
@@ -751,7 +1491,7 @@ impl TextEditor {
             self.autocomplete = None;
         }
 
-        self.refresh_search_matches();
+        self.content_changed();
         cx.notify();
     }
 
@@ -762,27 +1502,358 @@ impl TextEditor {
     /// - Otherwise: delete character before cursor
     /// - Does nothing if cursor is at document start
     fn handle_backspace(&mut self, _: &Backspace, _: &mut Window, cx: &mut Context<Self>) {
-        if self.handle_find_backspace(cx) {
+        if self.handle_find_input(InputCommand::Backspace, cx) {
             return;
         }
 
-        // Close autocomplete on backspace
-        self.autocomplete = None;
+        self.for_each_cursor(|editor, _| {
+            let start = editor.content[..editor.cursor_position]
+                .char_indices()
+                .next_back()
+                .map_or(0, |(idx, _)| idx);
+            editor.delete_selection_or(start..editor.cursor_position, cx);
+        });
+    }
 
-        if !self.delete_selection() {
-            if self.cursor_position > 0 {
-                self.cursor_position -= 1;
-                self.content.remove(self.cursor_position);
-                self.is_dirty = true;
-            }
-        } else {
-            self.is_dirty = true;
+    /// Handles Delete: deletes the selection or the character after the cursor.
+    fn handle_delete(&mut self, _: &Delete, _: &mut Window, cx: &mut Context<Self>) {
+        if self.handle_find_input(InputCommand::Delete, cx) {
+            return;
         }
-        self.refresh_search_matches();
+
+        self.for_each_cursor(|editor, _| {
+            let end = editor.content[editor.cursor_position..]
+                .chars()
+                .next()
+                .map_or(editor.cursor_position, |ch| {
+                    editor.cursor_position + ch.len_utf8()
+                });
+            editor.delete_selection_or(editor.cursor_position..end, cx);
+        });
+    }
+
+    /// Handles Ctrl+Backspace: deletes the selection or back to the previous word start.
+    fn handle_delete_word_backward(
+        &mut self,
+        _: &DeleteWordBackward,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.handle_find_input(InputCommand::DeleteWordBackward, cx) {
+            return;
+        }
+
+        self.for_each_cursor(|editor, _| {
+            let start = selection::prev_word_start(&editor.content, editor.cursor_position);
+            editor.delete_selection_or(start..editor.cursor_position, cx);
+        });
+    }
+
+    /// Handles Ctrl+Delete: deletes the selection or up to the next word end.
+    fn handle_delete_word_forward(
+        &mut self,
+        _: &DeleteWordForward,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.handle_find_input(InputCommand::DeleteWordForward, cx) {
+            return;
+        }
+
+        self.for_each_cursor(|editor, _| {
+            let end = selection::next_word_end(&editor.content, editor.cursor_position);
+            editor.delete_selection_or(editor.cursor_position..end, cx);
+        });
+    }
+
+    /// Handles Ctrl+K Ctrl+K: deletes the selection or the rest of the line.
+    ///
+    /// At the end of a line, joins the next line instead.
+    fn handle_delete_to_line_end(
+        &mut self,
+        _: &DeleteToLineEnd,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.for_each_cursor(|editor, _| {
+            let rest = &editor.content[editor.cursor_position..];
+            let end = match rest.find('\n') {
+                Some(0) => editor.cursor_position + 1,
+                Some(idx) => editor.cursor_position + idx,
+                None => editor.content.len(),
+            };
+            editor.delete_selection_or(editor.cursor_position..end, cx);
+        });
+    }
+
+    /// Handles Ctrl+Shift+K: deletes every line touched by the selection, or the
+    /// current line, keeping the cursor's column on the line that moves up.
+    ///
+    /// With several cursors, each line is deleted once however many cursors it has.
+    fn handle_delete_line(&mut self, _: &DeleteLine, _: &mut Window, cx: &mut Context<Self>) {
+        let mut cursors = cursors::merge(self.cursors(), self.content.len());
+        cursors.dedup_by_key(|(cursor, _)| selection::line_range(&self.content, cursor.head).start);
+        self.set_cursors(cursors);
+        self.for_each_cursor(|editor, _| editor.delete_current_lines(cx));
+    }
+
+    /// Handles Tab: indents the selected lines, or nests the list item at the
+    /// caret. Inside the text of any other line, inserts one level at the caret.
+    fn handle_indent_lines(&mut self, _: &IndentLines, _: &mut Window, cx: &mut Context<Self>) {
+        // Tab moves between the find panel's inputs while it is open
+        if self.find_panel.is_some() {
+            self.cycle_find_input(false, cx);
+            return;
+        }
+        let line = selection::line_range(&self.content, self.cursor_position);
+        let text = &self.content[line.clone()];
+        let in_text = self.get_selection_range().is_none()
+            && ListItem::parse(text).is_none()
+            && self.cursor_position - line.start > MarkdownHighlighter::text_start(text);
+        if in_text {
+            let unit = self.indent.text();
+            self.autocomplete = None;
+            self.for_each_cursor(|editor, _| editor.insert_text(&unit, cx));
+            return;
+        }
+        self.shift_indent(false, cx);
+    }
+
+    /// Handles Shift+Tab: outdents the selected lines, or un-nests the list
+    /// item at the caret.
+    fn handle_outdent_lines(&mut self, _: &OutdentLines, _: &mut Window, cx: &mut Context<Self>) {
+        if self.find_panel.is_some() {
+            self.cycle_find_input(true, cx);
+            return;
+        }
+        self.shift_indent(true, cx);
+    }
+
+    /// Moves to the next of the find panel's inputs, or with `reverse` (Shift+Tab)
+    /// the previous one, wrapping around; only when the replace input is shown.
+    fn cycle_find_input(&mut self, reverse: bool, cx: &mut Context<Self>) {
+        const ORDER: [ActiveInput; 2] = [ActiveInput::Query, ActiveInput::Replace];
+        let Some(panel) = self.find_panel.as_mut().filter(|panel| panel.show_replace) else {
+            return;
+        };
+        let current = ORDER
+            .iter()
+            .position(|input| *input == panel.active_input)
+            .unwrap_or(0);
+        let step = if reverse { ORDER.len() - 1 } else { 1 };
+        panel.set_active_input(ORDER[(current + step) % ORDER.len()]);
+        cx.notify();
+    }
+
+    /// Indents or outdents the lines of every caret and renumbers the ordered
+    /// lists they are in.
+    fn shift_indent(&mut self, outdent: bool, cx: &mut Context<Self>) {
+        self.autocomplete = None;
+        let unit = self.indent;
+        let mut edited = false;
+        self.for_each_cursor(|editor, _| {
+            let Some(edit) = indent::shift_lines(
+                &editor.content,
+                editor.selection_start,
+                editor.cursor_position,
+                unit,
+                outdent,
+            ) else {
+                return;
+            };
+            let start = edit.range.start;
+            editor.content.replace_range(edit.range, &edit.text);
+            editor.selection_start = edit.anchor;
+            editor.cursor_position = edit.head;
+            editor.renumber_list(start);
+            edited = true;
+        });
+        if !edited {
+            return;
+        }
+        self.is_dirty = true;
+        self.ensure_position_visible(self.cursor_position);
+        self.content_changed();
+        cx.notify();
+    }
+
+    /// Renumbers the ordered lists around `offset`, keeping the selection on its text.
+    fn renumber_list(&mut self, offset: usize) {
+        for (range, number) in lists::renumber(&self.content, offset).into_iter().rev() {
+            let delta = number.len() as isize - range.len() as isize;
+            if self.cursor_position >= range.end {
+                self.cursor_position = self.cursor_position.saturating_add_signed(delta);
+            }
+            self.selection_start = self.selection_start.map(|anchor| {
+                if anchor >= range.end {
+                    anchor.saturating_add_signed(delta)
+                } else {
+                    anchor
+                }
+            });
+            self.content.replace_range(range, &number);
+        }
+    }
+
+    /// Handles Alt+Up: moves the selected lines up past the line above. On a
+    /// heading line without a selection, moves the whole section instead, or
+    /// just the line when the section has no previous sibling.
+    fn handle_move_line_up(&mut self, _: &MoveLineUp, _: &mut Window, cx: &mut Context<Self>) {
+        if self.on_heading_line() && self.move_current_section(-1, cx) {
+            return;
+        }
+        let edit = lines::move_lines(
+            &self.content,
+            self.selection_start,
+            self.cursor_position,
+            -1,
+        );
+        self.apply_line_edit(edit, cx);
+    }
+
+    /// Handles Alt+Down: moves the selected lines down past the line below. On
+    /// a heading line without a selection, moves the whole section instead, or
+    /// just the line when the section has no next sibling.
+    fn handle_move_line_down(&mut self, _: &MoveLineDown, _: &mut Window, cx: &mut Context<Self>) {
+        if self.on_heading_line() && self.move_current_section(1, cx) {
+            return;
+        }
+        let edit = lines::move_lines(&self.content, self.selection_start, self.cursor_position, 1);
+        self.apply_line_edit(edit, cx);
+    }
+
+    /// Whether the caret is on a heading line with nothing selected.
+    fn on_heading_line(&self) -> bool {
+        if self.get_selection_range().is_some() {
+            return false;
+        }
+        // Only a line starting with `#` can be a heading; skip the lookup otherwise
+        let line = selection::line_range(&self.content, self.cursor_position);
+        self.content[line.start..].starts_with('#')
+            && self
+                .headings()
+                .iter()
+                .any(|heading| heading.offset == line.start)
+    }
+
+    /// Handles Ctrl+Shift+D: duplicates the selected lines below themselves.
+    fn handle_duplicate_line(&mut self, _: &DuplicateLine, _: &mut Window, cx: &mut Context<Self>) {
+        let edit =
+            lines::duplicate_lines(&self.content, self.selection_start, self.cursor_position);
+        self.apply_line_edit(Some(edit), cx);
+    }
+
+    /// Handles Ctrl+J: joins the selected lines, or the current line with the next.
+    fn handle_join_lines(&mut self, _: &JoinLines, _: &mut Window, cx: &mut Context<Self>) {
+        let edit = lines::join_lines(&self.content, self.selection_start, self.cursor_position);
+        self.apply_line_edit(edit, cx);
+    }
+
+    /// Sorts the selected lines, or the whole document without a selection.
+    fn handle_sort_lines(&mut self, _: &SortLines, _: &mut Window, cx: &mut Context<Self>) {
+        let edit = lines::sort_lines(&self.content, self.selection_start, self.cursor_position);
+        self.apply_line_edit(edit, cx);
+    }
+
+    /// Reverses the selected lines, or the whole document without a selection.
+    fn handle_reverse_lines(&mut self, _: &ReverseLines, _: &mut Window, cx: &mut Context<Self>) {
+        let edit = lines::reverse_lines(&self.content, self.selection_start, self.cursor_position);
+        self.apply_line_edit(edit, cx);
+    }
+
+    /// Removes repeated lines from the selection, or the whole document without one.
+    fn handle_unique_lines(&mut self, _: &UniqueLines, _: &mut Window, cx: &mut Context<Self>) {
+        let edit = lines::unique_lines(&self.content, self.selection_start, self.cursor_position);
+        self.apply_line_edit(edit, cx);
+    }
+
+    /// Handles Ctrl+T: swaps the characters around the caret.
+    fn handle_transpose_chars(
+        &mut self,
+        _: &TransposeChars,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let edit = lines::transpose_chars(&self.content, self.cursor_position);
+        self.apply_line_edit(edit, cx);
+    }
+
+    /// Applies a line command as one replacement of the document. Line commands
+    /// act on the primary selection, so the other carets are dropped.
+    fn apply_line_edit(&mut self, edit: Option<LineEdit>, cx: &mut Context<Self>) {
+        let Some(edit) = edit else {
+            return;
+        };
+        self.content.replace_range(edit.range, &edit.text);
+        self.selection_start = edit.anchor;
+        self.cursor_position = edit.head;
+        self.clear_extra_cursors();
+        self.autocomplete = None;
+        self.is_dirty = true;
+        self.ensure_position_visible(self.cursor_position);
+        self.content_changed();
         cx.notify();
     }
 
-    /// Handles Enter key press by inserting a newline at cursor position.
+    /// Deletes the lines touched by the selection, or the current line.
+    fn delete_current_lines(&mut self, cx: &mut Context<Self>) {
+        self.autocomplete = None;
+        let (start, end) = self
+            .get_selection_range()
+            .unwrap_or((self.cursor_position, self.cursor_position));
+        // A selection ending at the start of a line does not touch that line
+        let last = if end > start && self.content[..end].ends_with('\n') {
+            end - 1
+        } else {
+            end
+        };
+        let cursor_line = selection::line_range(&self.content, self.cursor_position);
+        let column = self.content[cursor_line.start..self.cursor_position]
+            .chars()
+            .count();
+
+        let mut range = selection::line_range(&self.content, start).start
+            ..selection::line_range(&self.content, last).end;
+        // The last line has no line break of its own; remove the one before it
+        if !self.content[range.clone()].ends_with('\n') && range.start > 0 {
+            range.start -= 1;
+        }
+        if range.is_empty() {
+            return;
+        }
+        self.content.drain(range.clone());
+        self.clear_selection();
+
+        let line = selection::line_range(&self.content, range.start);
+        let text = self.content[line.clone()].trim_end_matches('\n');
+        self.cursor_position = line.start
+            + text
+                .char_indices()
+                .nth(column)
+                .map_or(text.len(), |(idx, _)| idx);
+        self.is_dirty = true;
+        self.content_changed();
+        cx.notify();
+    }
+
+    /// Deletes the selection if there is one, otherwise `range`, leaving the
+    /// cursor where the deleted text started.
+    fn delete_selection_or(&mut self, range: std::ops::Range<usize>, cx: &mut Context<Self>) {
+        self.autocomplete = None;
+        if !self.delete_selection() {
+            if range.is_empty() {
+                return;
+            }
+            self.content.drain(range.clone());
+            self.cursor_position = range.start;
+        }
+        self.is_dirty = true;
+        self.content_changed();
+        cx.notify();
+    }
+
+    /// Handles Enter key press by inserting a newline at cursor position,
+    /// continuing any list or blockquote.
     /// If autocomplete is active, accepts the selected suggestion instead.
     fn handle_enter(&mut self, _: &Enter, _: &mut Window, cx: &mut Context<Self>) {
         if self.suppress_next_enter {
@@ -793,57 +1864,97 @@ impl TextEditor {
         // If autocomplete is active, accept the selected suggestion
         if let Some(autocomplete) = &self.autocomplete {
             if let Some(suggestion) = autocomplete.get_selected() {
-                // Get the line start position
-                let line_start = self.content[..self.cursor_position]
-                    .rfind('\n')
-                    .map(|pos| pos + 1)
-                    .unwrap_or(0);
-
-                // Replace from line start to cursor with the suggestion
-                self.content.drain(line_start..self.cursor_position);
-                self.content.insert_str(line_start, &suggestion.insert_text);
-                self.cursor_position = line_start + suggestion.insert_text.len();
+                let insert_text = suggestion.insert_text.clone();
+                self.for_each_cursor(|editor, _| {
+                    // Get the line start position
+                    let line_start = editor.content[..editor.cursor_position]
+                        .rfind('\n')
+                        .map(|pos| pos + 1)
+                        .unwrap_or(0);
+
+                    // Replace from line start to cursor with the suggestion
+                    editor.content.drain(line_start..editor.cursor_position);
+                    editor.content.insert_str(line_start, &insert_text);
+                    editor.cursor_position = line_start + insert_text.len();
+                    editor.selection_start = None;
+                });
                 self.is_dirty = true;
             }
             self.autocomplete = None;
-            self.refresh_search_matches();
+            self.content_changed();
             cx.notify();
             return;
         }
 
-        self.content.insert(self.cursor_position, '\n');
-        self.cursor_position += 1;
-        self.is_dirty = true;
-        self.refresh_search_matches();
-        cx.notify();
+        self.for_each_cursor(|editor, _| editor.insert_newline(cx));
+    }
+
+    /// Inserts a line break, continuing the list item, blockquote or
+    /// indentation of the current line when the caret is past its prefix.
+    /// On an item or quote line with no text, ends the list or quote instead.
+    fn insert_newline(&mut self, cx: &mut Context<Self>) {
+        self.delete_selection();
+        let line = selection::line_range(&self.content, self.cursor_position);
+        let line_end = line.start + self.content[line.clone()].trim_end_matches('\n').len();
+        let text = &self.content[line.start..line_end];
+        let in_text = self.cursor_position - line.start >= MarkdownHighlighter::text_start(text);
+
+        match lists::continuation(text).filter(|_| in_text) {
+            Some(Continuation::Next(prefix)) => self.insert_text(&format!("\n{prefix}"), cx),
+            Some(Continuation::End(prefix)) => {
+                self.content.replace_range(line.start..line_end, &prefix);
+                self.cursor_position = line.start + prefix.len();
+                self.is_dirty = true;
+                self.content_changed();
+                cx.notify();
+            }
+            None => self.insert_text("\n", cx),
+        }
     }
 
     /// Moves cursor left by one character.
     /// Clears any active selection (standard non-shift arrow key behavior).
     fn handle_move_left(&mut self, _: &MoveLeft, _: &mut Window, cx: &mut Context<Self>) {
-        self.autocomplete = None;
-        self.clear_selection();
-        if self.cursor_position > 0 {
-            self.cursor_position -= 1;
-            cx.notify();
+        if self.handle_find_input(InputCommand::MoveLeft, cx) {
+            return;
         }
+
+        self.autocomplete = None;
+        self.for_each_cursor(|editor, _| {
+            editor.clear_selection();
+            if editor.cursor_position > 0 {
+                editor.cursor_position = editor.skip_folded(editor.cursor_position - 1, false);
+            }
+        });
+        cx.notify();
     }
 
     /// Moves cursor right by one character.
     /// Clears any active selection (standard non-shift arrow key behavior).
     fn handle_move_right(&mut self, _: &MoveRight, _: &mut Window, cx: &mut Context<Self>) {
-        self.autocomplete = None;
-        self.clear_selection();
-        if self.cursor_position < self.content.len() {
-            self.cursor_position += 1;
-            cx.notify();
+        if self.handle_find_input(InputCommand::MoveRight, cx) {
+            return;
         }
+
+        self.autocomplete = None;
+        self.for_each_cursor(|editor, _| {
+            editor.clear_selection();
+            if editor.cursor_position < editor.content.len() {
+                editor.cursor_position = editor.skip_folded(editor.cursor_position + 1, true);
+            }
+        });
+        cx.notify();
     }
 
     /// Moves cursor up one line, maintaining horizontal column position when possible.
     /// Clears any active selection.
     /// If autocomplete is active, navigates suggestions instead.
+    /// If the find panel is open, recalls older search history instead.
     fn handle_move_up(&mut self, _: &MoveUp, _: &mut Window, cx: &mut Context<Self>) {
+        if self.handle_find_history(-1, cx) {
+            return;
+        }
+
         // If autocomplete is active, navigate suggestions
         if let Some(ref mut autocomplete) = self.autocomplete {
             autocomplete.move_up();
@@ -851,15 +1962,22 @@ impl TextEditor {
             return;
         }
 
-        self.clear_selection();
-        self.move_up_internal();
+        self.for_each_cursor(|editor, _| {
+            editor.clear_selection();
+            editor.move_up_internal();
+        });
         cx.notify();
     }
 
     /// Moves cursor down one line, maintaining horizontal column position when possible.
     /// Clears any active selection.
     /// If autocomplete is active, navigates suggestions instead.
+    /// If the find panel is open, recalls newer search history instead.
     fn handle_move_down(&mut self, _: &MoveDown, _: &mut Window, cx: &mut Context<Self>) {
+        if self.handle_find_history(1, cx) {
+            return;
+        }
+
         // If autocomplete is active, navigate suggestions
         if let Some(ref mut autocomplete) = self.autocomplete {
             autocomplete.move_down();
@@ -867,8 +1985,10 @@ impl TextEditor {
             return;
         }
 
-        self.clear_selection();
-        self.move_down_internal();
+        self.for_each_cursor(|editor, _| {
+            editor.clear_selection();
+            editor.move_down_internal();
+        });
         cx.notify();
     }
 
@@ -883,7 +2003,7 @@ impl TextEditor {
     /// - Stdin prompt is blocking and non-ideal for GUI application
     /// - Consider implementing modal dialog for file path input
     /// - No dirty flag tracking or save confirmation yet
-    fn handle_save(&mut self, _: &Save, _: &mut Window, _cx: &mut Context<Self>) {
+    fn handle_save(&mut self, _: &Save, _: &mut Window, cx: &mut Context<Self>) {
         use std::io::{self, Write};
 
         let path = if let Some(ref current) = self.current_file {
@@ -908,9 +2028,15 @@ impl TextEditor {
         if let Err(e) = std::fs::write(&path, &self.content) {
             eprintln!("Failed to save file: {}", e);
         } else {
+            if self.current_file.is_none() {
+                self.folds.rename(std::path::Path::new(&path));
+            }
             self.current_file = Some(path.clone());
             self.is_dirty = false;
             println!("File saved to: {}", path);
+            self.workspace.update(cx, |index, cx| {
+                index.refresh_headings(std::path::Path::new(&path), cx);
+            });
         }
     }
 
@@ -922,7 +2048,12 @@ impl TextEditor {
     /// Handles Ctrl+C (Copy) action.
     /// Copies selected text to system clipboard. Does nothing if no selection.
     fn handle_copy(&mut self, _: &Copy, _: &mut Window, cx: &mut Context<Self>) {
+        if self.handle_find_input(InputCommand::Copy, cx) {
+            return;
+        }
+
         if let Some(text) = self.get_selected_text() {
+            self.block_clipboard = self.active_block().map(|_| text.clone());
             cx.write_to_clipboard(ClipboardItem::new_string(text));
         }
     }
@@ -934,26 +2065,69 @@ impl TextEditor {
     /// - Otherwise: insert clipboard content at cursor
     /// - Advances cursor to end of pasted text
     fn handle_paste(&mut self, _: &Paste, _: &mut Window, cx: &mut Context<Self>) {
+        if self.handle_find_input(InputCommand::Paste, cx) {
+            return;
+        }
+
         if let Some(clipboard_item) = cx.read_from_clipboard() {
             if let Some(text) = clipboard_item.text().map(|s| s.to_string()) {
-                self.delete_selection();
-                self.content.insert_str(self.cursor_position, &text);
-                self.cursor_position += text.len();
-                self.is_dirty = true;
-                self.refresh_search_matches();
-                cx.notify();
+                // One line per cursor when the counts match, as copied from several cursors
+                let lines: Vec<&str> = text.split('\n').collect();
+                let per_cursor = lines.len() > 1 && lines.len() == self.extra_cursors.len() + 1;
+                if !per_cursor && self.block_clipboard.as_ref() == Some(&text) {
+                    self.paste_block(&lines, cx);
+                    return;
+                }
+                self.for_each_cursor(|editor, index| {
+                    let text = if per_cursor {
+                        lines[index]
+                    } else {
+                        text.as_str()
+                    };
+                    editor.insert_text(text, cx);
+                });
             }
         }
     }
 
+    /// Pastes a copied block at the primary caret, one row per line from the
+    /// caret's column down, after deleting every selection.
+    fn paste_block(&mut self, rows: &[&str], cx: &mut Context<Self>) {
+        self.for_each_cursor(|editor, _| {
+            editor.delete_selection();
+        });
+        self.clear_extra_cursors();
+        self.cursor_position = column::insert_block(&mut self.content, self.cursor_position, rows);
+        self.is_dirty = true;
+        self.content_changed();
+        cx.notify();
+    }
+
+    /// Replaces the selection, if any, with `text` and moves the caret after it.
+    fn insert_text(&mut self, text: &str, cx: &mut Context<Self>) {
+        self.delete_selection();
+        self.content.insert_str(self.cursor_position, text);
+        self.cursor_position += text.len();
+        self.is_dirty = true;
+        self.content_changed();
+        cx.notify();
+    }
+
     /// Handles Ctrl+X (Cut) action.
     /// Copies selected text to clipboard and deletes it. Does nothing if no selection.
     fn handle_cut(&mut self, _: &Cut, _: &mut Window, cx: &mut Context<Self>) {
+        if self.handle_find_input(InputCommand::Cut, cx) {
+            return;
+        }
+
         if let Some(text) = self.get_selected_text() {
+            self.block_clipboard = self.active_block().map(|_| text.clone());
             cx.write_to_clipboard(ClipboardItem::new_string(text));
-            self.delete_selection();
+            self.for_each_cursor(|editor, _| {
+                editor.delete_selection();
+            });
             self.is_dirty = true;
-            self.refresh_search_matches();
+            self.content_changed();
             cx.notify();
         }
     }
@@ -961,103 +2135,1199 @@ impl TextEditor {
     /// Handles Shift+Left (Select Left) action.
     /// Extends or initiates selection while moving cursor left.
     fn handle_select_left(&mut self, _: &SelectLeft, _: &mut Window, cx: &mut Context<Self>) {
-        if self.selection_start.is_none() {
-            self.selection_start = Some(self.cursor_position);
-        }
-        if self.cursor_position > 0 {
-            self.cursor_position -= 1;
-            cx.notify();
+        if self.handle_find_input(InputCommand::SelectLeft, cx) {
+            return;
         }
+
+        self.for_each_cursor(|editor, _| {
+            if editor.selection_start.is_none() {
+                editor.selection_start = Some(editor.cursor_position);
+            }
+            if editor.cursor_position > 0 {
+                editor.cursor_position = editor.skip_folded(editor.cursor_position - 1, false);
+            }
+        });
+        cx.notify();
     }
 
     /// Handles Shift+Right (Select Right) action.
     /// Extends or initiates selection while moving cursor right.
     fn handle_select_right(&mut self, _: &SelectRight, _: &mut Window, cx: &mut Context<Self>) {
-        if self.selection_start.is_none() {
-            self.selection_start = Some(self.cursor_position);
-        }
-        if self.cursor_position < self.content.len() {
-            self.cursor_position += 1;
-            cx.notify();
+        if self.handle_find_input(InputCommand::SelectRight, cx) {
+            return;
         }
+
+        self.for_each_cursor(|editor, _| {
+            if editor.selection_start.is_none() {
+                editor.selection_start = Some(editor.cursor_position);
+            }
+            if editor.cursor_position < editor.content.len() {
+                editor.cursor_position = editor.skip_folded(editor.cursor_position + 1, true);
+            }
+        });
+        cx.notify();
     }
 
     /// Handles Shift+Up (Select Up) action.
     /// Extends or initiates selection while moving cursor up one line.
     fn handle_select_up(&mut self, _: &SelectUp, _: &mut Window, cx: &mut Context<Self>) {
-        if self.selection_start.is_none() {
-            self.selection_start = Some(self.cursor_position);
-        }
-        self.move_up_internal();
+        self.for_each_cursor(|editor, _| {
+            if editor.selection_start.is_none() {
+                editor.selection_start = Some(editor.cursor_position);
+            }
+            editor.move_up_internal();
+        });
         cx.notify();
     }
 
     /// Handles Shift+Down (Select Down) action.
     /// Extends or initiates selection while moving cursor down one line.
     fn handle_select_down(&mut self, _: &SelectDown, _: &mut Window, cx: &mut Context<Self>) {
-        if self.selection_start.is_none() {
-            self.selection_start = Some(self.cursor_position);
+        self.for_each_cursor(|editor, _| {
+            if editor.selection_start.is_none() {
+                editor.selection_start = Some(editor.cursor_position);
+            }
+            editor.move_down_internal();
+        });
+        cx.notify();
+    }
+
+    /// Handles Ctrl+A (Select All) action.
+    /// Selects entire document content.
+    fn handle_select_all(&mut self, _: &SelectAll, _: &mut Window, cx: &mut Context<Self>) {
+        if self.handle_find_input(InputCommand::SelectAll, cx) {
+            return;
+        }
+
+        self.clear_extra_cursors();
+        self.selection_start = Some(0);
+        self.cursor_position = self.content.len();
+        cx.notify();
+    }
+
+    fn handle_toggle_find(&mut self, _: &ToggleFind, _: &mut Window, cx: &mut Context<Self>) {
+        if self.find_panel.is_some() {
+            self.close_find_panel();
+        } else {
+            self.open_find_panel();
+            self.focus_current_search_match();
         }
-        self.move_down_internal();
         cx.notify();
     }
 
-    /// Handles Ctrl+A (Select All) action.
-    /// Selects entire document content.
-    fn handle_select_all(&mut self, _: &SelectAll, _: &mut Window, cx: &mut Context<Self>) {
-        self.selection_start = Some(0);
-        self.cursor_position = self.content.len();
-        cx.notify();
-    }
+    fn handle_find_next(&mut self, _: &FindNext, _: &mut Window, cx: &mut Context<Self>) {
+        if self.find_panel.is_none() {
+            self.open_find_panel();
+            if self.focus_current_search_match() {
+                cx.notify();
+            }
+            return;
+        }
+
+        if let Some(range) = self.advance_search(1) {
+            self.focus_match(range);
+            cx.notify();
+        }
+    }
+
+    fn handle_find_previous(&mut self, _: &FindPrevious, _: &mut Window, cx: &mut Context<Self>) {
+        if self.find_panel.is_none() {
+            self.open_find_panel();
+            if self.focus_current_search_match() {
+                cx.notify();
+            }
+            return;
+        }
+
+        if let Some(range) = self.advance_search(-1) {
+            self.focus_match(range);
+            cx.notify();
+        }
+    }
+
+    /// Handles Ctrl+P (Toggle Palette) action.
+    /// Opens or closes the command palette for fuzzy file finding.
+    fn handle_toggle_palette(
+        &mut self,
+        _: &TogglePalette,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.toggle_palette("", window, cx);
+    }
+
+    /// Handles Ctrl+Shift+P (Toggle Command Palette) action.
+    /// Opens the palette in command mode by prefilling the `>` prefix.
+    fn handle_toggle_command_palette(
+        &mut self,
+        _: &ToggleCommandPalette,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.toggle_palette(">", window, cx);
+    }
+
+    /// Handles Ctrl+Shift+O (Go to Symbol) action.
+    /// Opens the palette listing the headings of the current buffer.
+    fn handle_go_to_symbol(&mut self, _: &GoToSymbol, window: &mut Window, cx: &mut Context<Self>) {
+        self.toggle_palette("@", window, cx);
+    }
+
+    /// Handles Ctrl+Alt+O (Toggle Outline) action.
+    fn handle_toggle_outline(&mut self, _: &ToggleOutline, _: &mut Window, cx: &mut Context<Self>) {
+        self.show_outline = !self.show_outline;
+        cx.notify();
+    }
+
+    /// Handles Ctrl+Shift+E (Toggle File Tree) action.
+    ///
+    /// Shows the tree and focuses it with the current file selected; when the
+    /// tree already has focus, hides it and returns to the text.
+    fn handle_toggle_file_tree(
+        &mut self,
+        _: &ToggleFileTree,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if !self.file_tree.visible {
+            self.file_tree.visible = true;
+            if let Some(path) = &self.current_file {
+                let path = std::path::PathBuf::from(path);
+                self.file_tree.reveal(&path.canonicalize().unwrap_or(path));
+            }
+            window.focus(&self.file_tree.focus_handle);
+        } else if self.file_tree.focus_handle.is_focused(window) {
+            self.file_tree.visible = false;
+            self.file_tree.prompt = None;
+            self.file_tree.context_menu = None;
+            window.focus(&self.focus_handle);
+        } else {
+            window.focus(&self.file_tree.focus_handle);
+        }
+        cx.notify();
+    }
+
+    /// Acts on what the file tree asked for after a key or context action.
+    fn handle_tree_outcome(
+        &mut self,
+        outcome: TreeOutcome,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        match outcome {
+            TreeOutcome::Ignored | TreeOutcome::Handled => {}
+            TreeOutcome::Open(path) => {
+                window.focus(&self.focus_handle);
+                self.load_file(path, cx);
+            }
+            TreeOutcome::Operation(operation) => {
+                let created = matches!(operation, FileOperation::Create(_));
+                match self.apply_file_operation(operation, cx) {
+                    // A new document is ready for typing right away
+                    Ok(()) if created => window.focus(&self.focus_handle),
+                    Ok(()) => {}
+                    Err(message) => self.file_tree.error = Some(message),
+                }
+            }
+            TreeOutcome::Leave => window.focus(&self.focus_handle),
+        }
+        cx.notify();
+    }
+
+    /// Selects a clicked tree row; folders expand or collapse, files open.
+    fn click_tree_row(
+        &mut self,
+        path: std::path::PathBuf,
+        is_dir: bool,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.file_tree.context_menu = None;
+        self.file_tree.selected = Some(path.clone());
+        if is_dir {
+            self.file_tree.toggle(&path);
+            window.focus(&self.file_tree.focus_handle);
+        } else {
+            window.focus(&self.focus_handle);
+            self.load_file(path, cx);
+        }
+        cx.notify();
+    }
+
+    /// Renders the file tree sidebar.
+    ///
+    /// Rows come from the workspace index, so the tree follows changes on disk.
+    /// The open file is highlighted and marked with a dot while it has unsaved
+    /// changes. Right-clicking a row (or the empty space below) opens its context menu.
+    fn render_file_tree(&self, window: &Window, cx: &mut Context<Self>) -> gpui::Div {
+        let rows = {
+            let index = self.workspace.read(cx);
+            self.file_tree
+                .visible_rows(index.directories(), index.files())
+        };
+        let focused = self.file_tree.focus_handle.is_focused(window);
+        let open_file = self.current_file.as_ref().map(|path| {
+            let path = std::path::PathBuf::from(path);
+            path.canonicalize().unwrap_or(path)
+        });
+
+        let context_menu = |cx: &mut Context<Self>| {
+            div()
+                .mx_2()
+                .my_1()
+                .bg(rgb(0x1f1f1f))
+                .border_1()
+                .border_color(rgb(0x454545))
+                .rounded_sm()
+                .shadow_lg()
+                .flex()
+                .flex_col()
+                .children(TreeAction::ALL.iter().enumerate().map(|(idx, &action)| {
+                    div()
+                        .id(("file-tree-action", idx))
+                        .px_2()
+                        .py_1()
+                        .flex()
+                        .flex_row()
+                        .justify_between()
+                        .hover(|style| style.bg(rgb(0x094771)))
+                        .child(action.label())
+                        .child(
+                            div()
+                                .text_xs()
+                                .text_color(rgb(0x808080))
+                                .child(action.keys()),
+                        )
+                        .on_click(cx.listener(move |editor, _: &ClickEvent, window, cx| {
+                            window.focus(&editor.file_tree.focus_handle);
+                            let outcome = editor.file_tree.begin(action);
+                            editor.handle_tree_outcome(outcome, window, cx);
+                        }))
+                }))
+        };
+
+        let mut list = div().flex().flex_col().overflow_hidden();
+        for (idx, row) in rows.iter().enumerate() {
+            let is_selected = self.file_tree.selected.as_ref() == Some(&row.path);
+            let is_open = open_file.as_ref() == Some(&row.path);
+            let marker = match (row.is_dir, row.expanded) {
+                (true, true) => "▾ ",
+                (true, false) => "▸ ",
+                (false, _) => "  ",
+            };
+            let click_path = row.path.clone();
+            let menu_path = row.path.clone();
+            let is_dir = row.is_dir;
+
+            list = list.child(
+                div()
+                    .id(("file-tree-row", idx))
+                    .flex()
+                    .flex_row()
+                    .py(px(2.0))
+                    .pr_2()
+                    .pl(px(8.0 + row.depth as f32 * 12.0))
+                    .text_color(if is_open {
+                        rgb(0xffffff)
+                    } else {
+                        rgb(0xb0b0b0)
+                    })
+                    .when(is_selected, |item| {
+                        item.bg(if focused {
+                            rgb(0x094771)
+                        } else {
+                            rgb(0x37373d)
+                        })
+                    })
+                    .hover(|style| style.bg(rgb(0x3a3a3a)))
+                    .child(
+                        div()
+                            .flex_1()
+                            .overflow_hidden()
+                            .whitespace_nowrap()
+                            .child(format!("{}{}", marker, row.name)),
+                    )
+                    .when(is_open && self.is_dirty, |item| {
+                        item.child(div().text_color(rgb(0xF8C555)).child("●"))
+                    })
+                    .on_click(cx.listener(move |editor, _: &ClickEvent, window, cx| {
+                        editor.click_tree_row(click_path.clone(), is_dir, window, cx);
+                    }))
+                    .on_mouse_down(
+                        gpui::MouseButton::Right,
+                        cx.listener(move |editor, _: &MouseDownEvent, window, cx| {
+                            editor.file_tree.selected = Some(menu_path.clone());
+                            editor.file_tree.context_menu = Some(menu_path.clone());
+                            window.focus(&editor.file_tree.focus_handle);
+                            cx.notify();
+                        }),
+                    ),
+            );
+            if self.file_tree.context_menu.as_ref() == Some(&row.path) {
+                list = list.child(context_menu(cx));
+            }
+        }
+
+        let root_menu_open = self.file_tree.context_menu.as_deref() == Some(self.file_tree.root());
+        let is_empty = rows.is_empty();
+
+        div()
+            .track_focus(&self.file_tree.focus_handle)
+            .on_key_down(
+                cx.listener(move |editor, event: &KeyDownEvent, window, cx| {
+                    let outcome = editor.file_tree.handle_key(event, &rows, cx);
+                    if !matches!(outcome, TreeOutcome::Ignored) {
+                        cx.stop_propagation();
+                    }
+                    editor.handle_tree_outcome(outcome, window, cx);
+                }),
+            )
+            .on_action(cx.listener(Self::handle_toggle_file_tree))
+            .on_action(cx.listener(Self::handle_toggle_outline))
+            .on_action(cx.listener(Self::handle_toggle_palette))
+            .on_action(cx.listener(Self::handle_toggle_command_palette))
+            .on_action(cx.listener(Self::handle_save))
+            .on_action(cx.listener(Self::handle_quit))
+            .flex()
+            .flex_col()
+            .w(px(FILE_TREE_WIDTH))
+            .h_full()
+            .flex_none()
+            .bg(rgb(0x252526))
+            .border_r_1()
+            .border_color(rgb(0x454545))
+            .font_family("monospace")
+            .text_sm()
+            .child(
+                div()
+                    .p_2()
+                    .text_xs()
+                    .text_color(rgb(0x808080))
+                    .child("FILES"),
+            )
+            .when_some(self.file_tree.prompt.as_ref(), |panel, prompt| {
+                let location = prompt
+                    .target
+                    .strip_prefix(self.file_tree.root())
+                    .unwrap_or(&prompt.target)
+                    .to_string_lossy()
+                    .to_string();
+                let label = match prompt.action {
+                    TreeAction::NewFile => format!("New file in /{}", location),
+                    TreeAction::NewFolder => format!("New folder in /{}", location),
+                    TreeAction::Rename => format!("Rename {}", location),
+                    TreeAction::Delete => format!("Move {} to the trash?", location),
+                };
+                let body = if prompt.action == TreeAction::Delete {
+                    div().text_xs().child("Enter to delete | Esc to cancel")
+                } else {
+                    prompt.input.render("Name", focused)
+                };
+                panel.child(
+                    div()
+                        .mx_2()
+                        .mb_2()
+                        .p_2()
+                        .bg(rgb(0x1f1f1f))
+                        .rounded_sm()
+                        .flex()
+                        .flex_col()
+                        .gap_1()
+                        .child(div().text_xs().text_color(rgb(0x808080)).child(label))
+                        .child(body),
+                )
+            })
+            .when_some(self.file_tree.error.as_ref(), |panel, error| {
+                panel.child(
+                    div()
+                        .mx_2()
+                        .mb_2()
+                        .text_xs()
+                        .text_color(rgb(0xF48771))
+                        .child(error.clone()),
+                )
+            })
+            .child(list)
+            .when(is_empty, |panel| {
+                panel.child(
+                    div()
+                        .p_2()
+                        .text_xs()
+                        .text_color(rgb(0x808080))
+                        .child("No documents"),
+                )
+            })
+            .when(root_menu_open, |panel| panel.child(context_menu(cx)))
+            // Empty space below the rows acts on the workspace root
+            .child(
+                div()
+                    .id("file-tree-end")
+                    .flex_1()
+                    .min_h(px(24.0))
+                    .on_mouse_down(
+                        gpui::MouseButton::Right,
+                        cx.listener(|editor, _: &MouseDownEvent, window, cx| {
+                            editor.file_tree.selected = None;
+                            editor.file_tree.context_menu =
+                                Some(editor.file_tree.root().to_path_buf());
+                            window.focus(&editor.file_tree.focus_handle);
+                            cx.notify();
+                        }),
+                    ),
+            )
+    }
+
+    /// Handles Home: smart home, see `smart_home_offset`.
+    fn handle_move_home(&mut self, _: &MoveHome, _: &mut Window, cx: &mut Context<Self>) {
+        self.apply_motion(Motion::Home, false, cx);
+    }
+
+    /// Handles End: moves to the end of the screen row, then of the line.
+    fn handle_move_end(&mut self, _: &MoveEnd, _: &mut Window, cx: &mut Context<Self>) {
+        self.apply_motion(Motion::End, false, cx);
+    }
+
+    /// Handles Shift+Home: extends the selection to the smart home position.
+    fn handle_select_home(&mut self, _: &SelectHome, _: &mut Window, cx: &mut Context<Self>) {
+        self.apply_motion(Motion::Home, true, cx);
+    }
+
+    /// Handles Shift+End: extends the selection to the end of the row or line.
+    fn handle_select_end(&mut self, _: &SelectEnd, _: &mut Window, cx: &mut Context<Self>) {
+        self.apply_motion(Motion::End, true, cx);
+    }
+
+    /// Handles Ctrl+Left: moves to the start of the previous word.
+    fn handle_move_word_left(&mut self, _: &MoveWordLeft, _: &mut Window, cx: &mut Context<Self>) {
+        self.apply_motion(Motion::WordLeft, false, cx);
+    }
+
+    /// Handles Ctrl+Right: moves to the end of the next word.
+    fn handle_move_word_right(
+        &mut self,
+        _: &MoveWordRight,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.apply_motion(Motion::WordRight, false, cx);
+    }
+
+    /// Handles Ctrl+Shift+Left: extends the selection to the previous word start.
+    fn handle_select_word_left(
+        &mut self,
+        _: &SelectWordLeft,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.apply_motion(Motion::WordLeft, true, cx);
+    }
+
+    /// Handles Ctrl+Shift+Right: extends the selection to the next word end.
+    fn handle_select_word_right(
+        &mut self,
+        _: &SelectWordRight,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.apply_motion(Motion::WordRight, true, cx);
+    }
+
+    /// Handles Ctrl+Home: moves to the start of the document.
+    fn handle_move_to_start(&mut self, _: &MoveToStart, _: &mut Window, cx: &mut Context<Self>) {
+        self.apply_motion(Motion::DocumentStart, false, cx);
+    }
+
+    /// Handles Ctrl+End: moves to the end of the document.
+    fn handle_move_to_end(&mut self, _: &MoveToEnd, _: &mut Window, cx: &mut Context<Self>) {
+        self.apply_motion(Motion::DocumentEnd, false, cx);
+    }
+
+    /// Handles Ctrl+Shift+Home: selects to the start of the document.
+    fn handle_select_to_start(
+        &mut self,
+        _: &SelectToStart,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.apply_motion(Motion::DocumentStart, true, cx);
+    }
+
+    /// Handles Ctrl+Shift+End: selects to the end of the document.
+    fn handle_select_to_end(&mut self, _: &SelectToEnd, _: &mut Window, cx: &mut Context<Self>) {
+        self.apply_motion(Motion::DocumentEnd, true, cx);
+    }
+
+    /// Handles PageUp: scrolls and moves up one viewport.
+    fn handle_page_up(&mut self, _: &PageUp, _: &mut Window, cx: &mut Context<Self>) {
+        self.apply_motion(Motion::PageUp, false, cx);
+    }
+
+    /// Handles PageDown: scrolls and moves down one viewport.
+    fn handle_page_down(&mut self, _: &PageDown, _: &mut Window, cx: &mut Context<Self>) {
+        self.apply_motion(Motion::PageDown, false, cx);
+    }
+
+    /// Handles Shift+PageUp: extends the selection up one viewport.
+    fn handle_select_page_up(&mut self, _: &SelectPageUp, _: &mut Window, cx: &mut Context<Self>) {
+        self.apply_motion(Motion::PageUp, true, cx);
+    }
+
+    /// Handles Shift+PageDown: extends the selection down one viewport.
+    fn handle_select_page_down(
+        &mut self,
+        _: &SelectPageDown,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.apply_motion(Motion::PageDown, true, cx);
+    }
+
+    /// Handles Ctrl+Up: moves to the blank line before the paragraph.
+    fn handle_move_paragraph_up(
+        &mut self,
+        _: &MoveParagraphUp,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.apply_motion(Motion::ParagraphUp, false, cx);
+    }
+
+    /// Handles Ctrl+Down: moves to the blank line after the paragraph.
+    fn handle_move_paragraph_down(
+        &mut self,
+        _: &MoveParagraphDown,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.apply_motion(Motion::ParagraphDown, false, cx);
+    }
+
+    /// Handles Ctrl+Shift+Up: extends the selection to the previous paragraph break.
+    fn handle_select_paragraph_up(
+        &mut self,
+        _: &SelectParagraphUp,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.apply_motion(Motion::ParagraphUp, true, cx);
+    }
+
+    /// Handles Ctrl+Shift+Down: extends the selection to the next paragraph break.
+    fn handle_select_paragraph_down(
+        &mut self,
+        _: &SelectParagraphDown,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.apply_motion(Motion::ParagraphDown, true, cx);
+    }
+
+    /// Moves the caret by `motion`, extending the selection when `select` is set.
+    ///
+    /// Routed to the find panel's active input when the panel is open and the
+    /// motion has an equivalent there. The caret is scrolled into view.
+    fn apply_motion(&mut self, motion: Motion, select: bool, cx: &mut Context<Self>) {
+        let routed = motion
+            .input_command(select)
+            .is_some_and(|command| self.handle_find_input(command, cx));
+        if routed {
+            return;
+        }
+
+        self.autocomplete = None;
+        // Scroll once for all cursors, then move each by the same number of rows
+        let page_rows = match motion {
+            Motion::PageUp => -self.scroll_page(-1),
+            Motion::PageDown => self.scroll_page(1),
+            _ => 0,
+        };
+        self.for_each_cursor(|editor, _| editor.move_cursor(motion, select, page_rows));
+        self.ensure_position_visible(self.cursor_position);
+        cx.notify();
+    }
+
+    /// Moves the caret by `motion`; page motions move by `page_rows` rows.
+    fn move_cursor(&mut self, motion: Motion, select: bool, page_rows: isize) {
+        if !select {
+            self.clear_selection();
+        } else if self.selection_start.is_none() {
+            self.selection_start = Some(self.cursor_position);
+        }
+
+        self.cursor_position = match motion {
+            Motion::WordLeft => self.skip_folded(
+                selection::prev_word_start(&self.content, self.cursor_position),
+                false,
+            ),
+            Motion::WordRight => self.skip_folded(
+                selection::next_word_end(&self.content, self.cursor_position),
+                true,
+            ),
+            Motion::Home => self.smart_home_offset(),
+            Motion::End => self.end_offset(),
+            Motion::DocumentStart => 0,
+            Motion::DocumentEnd => self.content.len(),
+            Motion::PageUp | Motion::PageDown => {
+                self.offset_rows_away(self.cursor_position, page_rows)
+            }
+            Motion::ParagraphUp => self.skip_folded(
+                selection::paragraph_boundary(&self.content, self.cursor_position, false),
+                false,
+            ),
+            Motion::ParagraphDown => self.skip_folded(
+                selection::paragraph_boundary(&self.content, self.cursor_position, true),
+                true,
+            ),
+        };
+    }
+
+    /// Target of Home.
+    ///
+    /// On the first row of a line, toggles between where the text starts (after
+    /// indentation, list marker or blockquote prefix) and the start of the line.
+    /// On a wrapped continuation row, goes to the start of the row first.
+    fn smart_home_offset(&self) -> usize {
+        let rows = self.visual_rows();
+        let row = rows[wrap::row_at(&rows, self.cursor_position)];
+        let line = selection::line_range(&self.content, self.cursor_position);
+        if row.start != line.start && self.cursor_position != row.start {
+            return row.start;
+        }
+
+        let text = self.content[line.clone()].trim_end_matches('\n');
+        let text_start = line.start + MarkdownHighlighter::text_start(text);
+        if self.cursor_position == text_start {
+            line.start
+        } else {
+            text_start
+        }
+    }
+
+    /// Target of End: the end of the screen row, or of the whole line when the
+    /// caret is already at the end of a wrapped row.
+    fn end_offset(&self) -> usize {
+        let rows = self.visual_rows();
+        let row = rows[wrap::row_at(&rows, self.cursor_position)];
+        let row_end = self.offset_in_row(&row, usize::MAX);
+        if self.cursor_position != row_end || row.last {
+            return row_end;
+        }
+        self.content[self.cursor_position..]
+            .find('\n')
+            .map_or(self.content.len(), |idx| self.cursor_position + idx)
+    }
+
+    /// Scrolls by one viewport in `direction` and returns the number of rows in
+    /// a page, so carets can move by as much and keep their place on screen.
+    fn scroll_page(&mut self, direction: isize) -> isize {
+        let line_height = f32::from(self.text_metrics.line_height);
+        let page_rows =
+            ((f32::from(self.text_bounds.size.height) / line_height).floor() as isize - 1).max(1);
+        self.scroll_offset = (self.scroll_offset + (direction * page_rows) as f32 * line_height)
+            .clamp(0.0, self.max_scroll());
+        page_rows
+    }
+
+    /// Handles Alt+Z (Toggle Soft Wrap) action.
+    fn handle_toggle_soft_wrap(
+        &mut self,
+        _: &ToggleSoftWrap,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.soft_wrap = !self.soft_wrap;
+        cx.notify();
+    }
+
+    /// Handles Ctrl+Shift+[ (Fold) action.
+    /// Folds the innermost open region around the cursor.
+    fn handle_fold(&mut self, _: &Fold, _: &mut Window, cx: &mut Context<Self>) {
+        let layout = self.fold_layout();
+        let line = self.get_current_line_number() - 1;
+        let innermost = layout
+            .regions
+            .iter()
+            .filter(|region| region.start <= line && line <= region.end)
+            .filter(|region| !layout.folded.contains(region))
+            .max_by_key(|region| region.start);
+        if let Some(region) = innermost {
+            self.folds.fold(&self.content, region);
+            self.settle_cursor_after_fold();
+            cx.notify();
+        }
+    }
+
+    /// Handles Ctrl+Shift+] (Unfold) action.
+    /// Unfolds the folded region whose header holds the cursor.
+    fn handle_unfold(&mut self, _: &Unfold, _: &mut Window, cx: &mut Context<Self>) {
+        let line = self.get_current_line_number() - 1;
+        let folded = self.folded_regions();
+        if let Some(region) = folded.iter().find(|region| region.start == line) {
+            self.folds.unfold(&self.content, region);
+            cx.notify();
+        }
+    }
+
+    /// Handles Ctrl+K Ctrl+0 (Fold All) action.
+    fn handle_fold_all(&mut self, _: &FoldAll, _: &mut Window, cx: &mut Context<Self>) {
+        let layout = self.fold_layout();
+        self.folds.fold_all(&self.content, &layout.regions);
+        self.settle_cursor_after_fold();
+        cx.notify();
+    }
+
+    /// Handles Ctrl+K Ctrl+J (Unfold All) action.
+    fn handle_unfold_all(&mut self, _: &UnfoldAll, _: &mut Window, cx: &mut Context<Self>) {
+        self.folds.unfold_all();
+        cx.notify();
+    }
+
+    /// Folds or unfolds the region starting at `line`, from a gutter chevron.
+    fn toggle_fold(&mut self, line: usize, cx: &mut Context<Self>) {
+        let layout = self.fold_layout();
+        let Some(region) = layout.regions.iter().find(|region| region.start == line) else {
+            return;
+        };
+        if layout.folded.contains(region) {
+            self.folds.unfold(&self.content, region);
+        } else {
+            self.folds.fold(&self.content, region);
+            self.settle_cursor_after_fold();
+        }
+        cx.notify();
+    }
+
+    /// Moves a caret that was just folded away to the end of the fold's header line.
+    fn settle_cursor_after_fold(&mut self) {
+        let position = self.skip_folded(self.cursor_position, false);
+        if position != self.cursor_position {
+            self.cursor_position = position;
+            self.selection_start = None;
+        }
+    }
+
+    /// Handles Ctrl+K Ctrl+Up (Move Section Up) action.
+    /// Swaps the section containing the cursor with its previous sibling.
+    fn handle_move_section_up(
+        &mut self,
+        _: &MoveSectionUp,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.move_current_section(-1, cx);
+    }
+
+    /// Handles Ctrl+K Ctrl+Down (Move Section Down) action.
+    /// Swaps the section containing the cursor with its next sibling.
+    fn handle_move_section_down(
+        &mut self,
+        _: &MoveSectionDown,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.move_current_section(1, cx);
+    }
+
+    /// Swaps the section containing the cursor with its sibling in `direction`,
+    /// returning whether it moved.
+    fn move_current_section(&mut self, direction: isize, cx: &mut Context<Self>) -> bool {
+        self.edit_current_section(cx, |content, headings, index, cursor| {
+            outline::move_section(content, headings, index, direction, cursor)
+        })
+    }
+
+    /// Handles Ctrl+K Ctrl+Left (Promote Section) action.
+    /// Raises the current heading and every heading nested under it by one level.
+    fn handle_promote_section(
+        &mut self,
+        _: &PromoteSection,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.edit_current_section(cx, |content, headings, index, cursor| {
+            outline::shift_section_levels(content, headings, index, -1, cursor)
+        });
+    }
+
+    /// Handles Ctrl+K Ctrl+Right (Demote Section) action.
+    /// Lowers the current heading and every heading nested under it by one level.
+    fn handle_demote_section(&mut self, _: &DemoteSection, _: &mut Window, cx: &mut Context<Self>) {
+        self.edit_current_section(cx, |content, headings, index, cursor| {
+            outline::shift_section_levels(content, headings, index, 1, cursor)
+        });
+    }
+
+    /// Runs a restructuring edit on the section containing the cursor,
+    /// returning whether the document changed.
+    fn edit_current_section(
+        &mut self,
+        cx: &mut Context<Self>,
+        edit: impl FnOnce(&str, &[Heading], usize, usize) -> Option<OutlineEdit>,
+    ) -> bool {
+        let headings = self.headings();
+        let Some(index) = outline::section_at(&headings, self.cursor_position) else {
+            return false;
+        };
+        match edit(&self.content, &headings, index, self.cursor_position) {
+            Some(edit) => {
+                self.apply_outline_edit(edit, cx);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Moves a section dropped in the outline sidebar before `target` (or to the end).
+    fn drop_section(&mut self, index: usize, target: Option<usize>, cx: &mut Context<Self>) {
+        let headings = self.headings();
+        if index >= headings.len() || target.is_some_and(|target| target >= headings.len()) {
+            return;
+        }
+        if let Some(edit) = outline::move_section_to(
+            &self.content,
+            &headings,
+            index,
+            target,
+            self.cursor_position,
+        ) {
+            self.apply_outline_edit(edit, cx);
+        }
+    }
+
+    /// Replaces the document with a restructured version and keeps the caret in view.
+    fn apply_outline_edit(&mut self, edit: OutlineEdit, cx: &mut Context<Self>) {
+        self.content = edit.content;
+        self.cursor_position = edit.cursor;
+        self.selection_start = None;
+        self.clear_extra_cursors();
+        self.autocomplete = None;
+        self.is_dirty = true;
+        self.ensure_position_visible(self.cursor_position);
+        self.content_changed();
+        cx.notify();
+    }
+
+    /// Width taken by the sidebars left of the text, in pixels.
+    fn sidebar_width(&self) -> f32 {
+        let mut width = 0.0;
+        if self.file_tree.visible {
+            width += FILE_TREE_WIDTH;
+        }
+        if self.show_outline {
+            width += OUTLINE_WIDTH;
+        }
+        width
+    }
+
+    /// Renders the outline sidebar: one row per heading, indented by level.
+    ///
+    /// Clicking a row jumps to the heading; dragging a row onto another moves
+    /// the whole section before it, and dropping on the footer moves it to the end.
+    fn render_outline(&self, cx: &mut Context<Self>) -> gpui::Div {
+        let headings = self.headings();
+        let current = outline::section_at(&headings, self.cursor_position);
+
+        let rows = headings.iter().enumerate().map(|(idx, heading)| {
+            let offset = heading.offset;
+            let is_current = current == Some(idx);
+            let dragged = DraggedSection {
+                index: idx,
+                title: heading.title.clone(),
+            };
+
+            div()
+                .id(("outline-heading", idx))
+                .py_1()
+                .pr_2()
+                .pl(px(8.0 + (heading.level - 1) as f32 * 12.0))
+                .text_color(if is_current {
+                    rgb(0xffffff)
+                } else {
+                    rgb(0xb0b0b0)
+                })
+                .when(is_current, |row| row.bg(rgb(0x094771)))
+                .hover(|style| style.bg(rgb(0x3a3a3a)))
+                .overflow_hidden()
+                .whitespace_nowrap()
+                .child(heading.title.clone())
+                .on_click(cx.listener(move |editor, _: &ClickEvent, window, cx| {
+                    window.focus(&editor.focus_handle);
+                    editor.jump_to(offset, cx);
+                }))
+                .on_drag(dragged, |dragged, _, _, cx| cx.new(|_| dragged.clone()))
+                .drag_over::<DraggedSection>(|style, _, _, _| {
+                    style.border_t_2().border_color(rgb(0x569CD6))
+                })
+                .on_drop(cx.listener(move |editor, dragged: &DraggedSection, _, cx| {
+                    editor.drop_section(dragged.index, Some(idx), cx);
+                }))
+        });
+
+        div()
+            .flex()
+            .flex_col()
+            .w(px(OUTLINE_WIDTH))
+            .h_full()
+            .flex_none()
+            .bg(rgb(0x252526))
+            .border_r_1()
+            .border_color(rgb(0x454545))
+            .font_family("monospace")
+            .text_sm()
+            .child(
+                div()
+                    .p_2()
+                    .text_xs()
+                    .text_color(rgb(0x808080))
+                    .child("OUTLINE"),
+            )
+            .child(div().flex().flex_col().overflow_hidden().children(rows))
+            .when(headings.is_empty(), |panel| {
+                panel.child(
+                    div()
+                        .p_2()
+                        .text_xs()
+                        .text_color(rgb(0x808080))
+                        .child("No headings"),
+                )
+            })
+            // Remaining space is a drop zone for moving a section to the end
+            .child(
+                div()
+                    .id("outline-end")
+                    .flex_1()
+                    .min_h(px(24.0))
+                    .drag_over::<DraggedSection>(|style, _, _, _| {
+                        style.border_t_2().border_color(rgb(0x569CD6))
+                    })
+                    .on_drop(cx.listener(|editor, dragged: &DraggedSection, _, cx| {
+                        editor.drop_section(dragged.index, None, cx);
+                    })),
+            )
+    }
+
+    /// Invisible element filling the text viewport that records its bounds and
+    /// follows mouse selections.
+    ///
+    /// Clicks, scrolling and the wrap width all need the viewport's real size and
+    /// position, which are only known after layout. A change re-renders the
+    /// editor so wrapping follows window resizes and sidebar toggles. Drag and
+    /// release are observed window-wide, so a selection keeps following the
+    /// pointer after it leaves the text.
+    fn track_text_viewport(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let editor = cx.entity().downgrade();
+        let paint_editor = editor.clone();
+        canvas(
+            move |bounds, _, cx| {
+                cx.defer(move |cx| {
+                    editor
+                        .update(cx, |editor, cx| {
+                            if editor.text_bounds != bounds {
+                                editor.text_bounds = bounds;
+                                cx.notify();
+                            }
+                        })
+                        .ok();
+                });
+            },
+            move |_, _, window, _| {
+                window.on_mouse_event({
+                    let editor = paint_editor.clone();
+                    move |event: &MouseMoveEvent, phase, window, cx| {
+                        if phase == gpui::DispatchPhase::Bubble
+                            && event.pressed_button == Some(gpui::MouseButton::Left)
+                        {
+                            editor
+                                .update(cx, |editor, cx| {
+                                    editor.handle_mouse_drag(event.position, window, cx)
+                                })
+                                .ok();
+                        }
+                    }
+                });
+                window.on_mouse_event(move |event: &MouseUpEvent, phase, _, cx| {
+                    if phase == gpui::DispatchPhase::Bubble
+                        && event.button == gpui::MouseButton::Left
+                    {
+                        paint_editor
+                            .update(cx, |editor, cx| editor.handle_mouse_up(cx))
+                            .ok();
+                    }
+                });
+            },
+        )
+        .absolute()
+        .size_full()
+    }
+
+    /// Renders the document as screen rows.
+    ///
+    /// Each visible line is tokenized once and its styled runs are then laid out
+    /// on the line's wrapped rows. The caret at a wrap point is drawn at the start
+    /// of the following row. Lines that start a foldable region get a chevron in
+    /// the gutter; folded ones end with a `⋯` marker.
+    fn render_text_rows(&self, cx: &mut Context<Self>) -> gpui::Div {
+        let line_height = self.text_metrics.line_height;
+        let lines: Vec<&str> = self.content.split('\n').collect();
+        let mut current_pos = 0;
+        let mut result = div().flex().flex_col();
+        let cursors = cursors::merge(self.cursors(), self.content.len());
+        let heads: Vec<usize> = cursors.iter().map(|(cursor, _)| cursor.head).collect();
+        let selections: Vec<(usize, usize)> = cursors
+            .iter()
+            .map(|(cursor, _)| cursor.range())
+            .filter(|range| !range.is_empty())
+            .map(|range| (range.start, range.end))
+            .collect();
+
+        let layout = self.fold_layout();
+        let rows = &layout.rows;
+        let mut visible = layout.visible.iter().peekable();
+        let mut next_row = 0;
+        let fold_starts: std::collections::HashSet<usize> =
+            layout.regions.iter().map(|region| region.start).collect();
+        let folded_starts: std::collections::HashSet<usize> =
+            layout.folded.iter().map(|region| region.start).collect();
+
+        for (line_idx, line) in lines.into_iter().enumerate() {
+            let line_start = current_pos;
+            let line_end = current_pos + line.len();
+            current_pos = line_end + 1;
+            if visible.next_if_eq(&&line_idx).is_none() {
+                continue;
+            }
 
-    fn handle_toggle_find(&mut self, _: &ToggleFind, _: &mut Window, cx: &mut Context<Self>) {
-        if self.find_panel.is_some() {
-            self.close_find_panel();
-        } else {
-            self.open_find_panel();
-            self.focus_current_search_match();
-        }
-        cx.notify();
-    }
+            let line_rows = &rows[next_row
+                ..next_row
+                    + rows[next_row..]
+                        .iter()
+                        .take_while(|row| row.line == line_idx)
+                        .count()];
+            next_row += line_rows.len();
 
-    fn handle_find_next(&mut self, _: &FindNext, _: &mut Window, cx: &mut Context<Self>) {
-        if self.find_panel.is_none() {
-            self.open_find_panel();
-            if self.focus_current_search_match() {
-                cx.notify();
+            let line_heads: Vec<usize> = heads
+                .iter()
+                .copied()
+                .filter(|head| (line_start..=line_end).contains(head))
+                .collect();
+
+            // Styled runs and the carets for the whole line, in document order
+            let mut pieces = Vec::new();
+            let mut char_count = 0;
+            for (text, token_type) in MarkdownHighlighter::tokenize_line(line) {
+                let token_color = MarkdownHighlighter::get_color(&token_type);
+                let token_start = line_start + char_count;
+
+                pieces.extend(self.build_segments_for_token(
+                    &text,
+                    token_color,
+                    token_start,
+                    &selections,
+                    &line_heads,
+                    self.find_panel.as_ref(),
+                ));
+                char_count += text.len();
+            }
+            if line_heads.contains(&line_end) {
+                pieces.push(SegmentPiece::Cursor);
             }
-            return;
-        }
 
-        if let Some(range) = self.advance_search(1) {
-            self.focus_match(range);
-            cx.notify();
-        }
-    }
+            // Distribute the pieces over the rows, splitting runs at wrap points
+            let mut row_children: Vec<Vec<gpui::AnyElement>> =
+                line_rows.iter().map(|_| Vec::new()).collect();
+            let mut row = 0;
+            let mut position = line_start;
+            for piece in pieces {
+                match piece {
+                    SegmentPiece::Cursor => {
+                        while row + 1 < line_rows.len() && position >= line_rows[row].end {
+                            row += 1;
+                        }
+                        // Negative margins keep the caret from pushing the text aside
+                        row_children[row].push(
+                            div()
+                                .w(px(2.0))
+                                .mx(px(-1.0))
+                                .h(line_height)
+                                .bg(rgb(0xcccccc))
+                                .into_any_element(),
+                        );
+                    }
+                    SegmentPiece::Text(run) => {
+                        let mut rest = run.text.as_str();
+                        while !rest.is_empty() {
+                            while row + 1 < line_rows.len() && position >= line_rows[row].end {
+                                row += 1;
+                            }
+                            let take = if row + 1 < line_rows.len() {
+                                (line_rows[row].end - position).min(rest.len())
+                            } else {
+                                rest.len()
+                            };
+                            let mut node = div().text_color(run.text_color);
+                            if let Some(bg) = run.background {
+                                node = node.bg(bg);
+                            }
+                            row_children[row]
+                                .push(node.child(rest[..take].to_string()).into_any_element());
+                            rest = &rest[take..];
+                            position += take;
+                        }
+                    }
+                }
+            }
 
-    fn handle_find_previous(&mut self, _: &FindPrevious, _: &mut Window, cx: &mut Context<Self>) {
-        if self.find_panel.is_none() {
-            self.open_find_panel();
-            if self.focus_current_search_match() {
-                cx.notify();
+            let is_folded = folded_starts.contains(&line_idx);
+            if let (true, Some(last)) = (is_folded, row_children.last_mut()) {
+                last.push(
+                    div()
+                        .ml_2()
+                        .px_1()
+                        .rounded_sm()
+                        .bg(rgb(0x3a3a3a))
+                        .text_color(rgb(0x808080))
+                        .child("⋯")
+                        .into_any_element(),
+                );
             }
-            return;
-        }
 
-        if let Some(range) = self.advance_search(-1) {
-            self.focus_match(range);
-            cx.notify();
+            for (idx, (visual_row, children)) in line_rows.iter().zip(row_children).enumerate() {
+                // Gutter chevron for lines that start a foldable region
+                let mut gutter = div()
+                    .w(px(FOLD_GUTTER_WIDTH))
+                    .flex_none()
+                    .text_color(rgb(0x808080));
+                if idx == 0 && fold_starts.contains(&line_idx) {
+                    gutter = gutter
+                        .child(if is_folded { "▸" } else { "▾" })
+                        .hover(|style| style.text_color(rgb(0xd4d4d4)))
+                        .on_mouse_down(
+                            gpui::MouseButton::Left,
+                            cx.listener(move |editor, _: &MouseDownEvent, _, cx| {
+                                editor.toggle_fold(line_idx, cx);
+                                cx.stop_propagation();
+                            }),
+                        );
+                }
+
+                result = result.child(
+                    div().flex().flex_row().child(gutter).child(
+                        div()
+                            .flex()
+                            .flex_row()
+                            .h(line_height)
+                            .pl(self.text_metrics.char_width * visual_row.indent as f32)
+                            .children(children),
+                    ),
+                );
+            }
         }
+
+        result
     }
 
-    /// Handles Ctrl+P (Toggle Palette) action.
-    /// Opens or closes the command palette for fuzzy file finding.
-    fn handle_toggle_palette(
-        &mut self,
-        _: &TogglePalette,
-        window: &mut Window,
-        cx: &mut Context<Self>,
-    ) {
+    /// Opens the palette with `initial_query`, or closes it if already open.
+    fn toggle_palette(&mut self, initial_query: &str, window: &mut Window, cx: &mut Context<Self>) {
         if self.palette.is_some() {
             // Close palette and restore focus to editor
             self.palette = None;
@@ -1065,7 +3335,16 @@ impl TextEditor {
         } else {
             self.close_find_panel();
             // Open palette and transfer focus to it
-            let palette_entity = cx.new(|cx| Palette::new(self.working_dir.clone(), cx));
+            let workspace = self.workspace.clone();
+            let context = PaletteContext {
+                usage: self.open_history.usage(),
+                current_file: self.current_file.as_ref().map(|path| {
+                    let path = std::path::PathBuf::from(path);
+                    path.canonicalize().unwrap_or(path)
+                }),
+                headings: self.headings().to_vec(),
+            };
+            let palette_entity = cx.new(|cx| Palette::new(workspace, initial_query, context, cx));
             window.focus(&palette_entity.read(cx).focus_handle(cx));
             self.palette = Some(palette_entity);
         }
@@ -1076,82 +3355,345 @@ impl TextEditor {
     ///
     /// This method reads the file content and updates the editor state.
     /// Called when a file is selected from the palette.
-    fn load_file(&mut self, path: std::path::PathBuf, cx: &mut Context<Self>) {
+    /// Replaces the buffer with the file at `path`, returning whether it loaded.
+    fn load_file(&mut self, path: std::path::PathBuf, cx: &mut Context<Self>) -> bool {
         match std::fs::read_to_string(&path) {
             Ok(content) => {
                 self.content = content;
                 self.cursor_position = 0;
                 self.selection_start = None;
+                self.clear_extra_cursors();
                 self.scroll_offset = 0.0;
                 self.current_file = Some(path.to_string_lossy().to_string());
                 self.is_dirty = false;
+                self.folds = FoldState::load(Some(&path));
+                self.indent = if self.detect_indent {
+                    Indent::detect(&self.content).unwrap_or(self.configured_indent)
+                } else {
+                    self.configured_indent
+                };
+                self.open_history.record_open(&path);
                 println!("Loaded file: {}", path.display());
                 cx.notify();
+                true
             }
             Err(e) => {
                 eprintln!("Failed to load file: {}", e);
+                false
             }
         }
     }
 
-    /// Handles mouse click events for cursor positioning.
-    ///
-    /// Converts pixel coordinates to document position by:
-    /// 1. Calculating clicked line from Y coordinate
-    /// 2. Calculating column from X coordinate
-    /// 3. Converting (line, column) to byte offset
+    /// Applies a file operation chosen in the palette or the file tree.
     ///
-    /// # Magic Numbers
+    /// Keeps the buffer attached to its file: a moved file (or a file in a moved
+    /// folder) gets its new path as `current_file`, and a trashed file leaves an
+    /// unsaved buffer behind so the content can still be saved elsewhere. Newly
+    /// created files are opened, so creating one is refused while the buffer
+    /// has unsaved changes. A failure is returned as a message for the palette
+    /// or the tree to show.
+    fn apply_file_operation(
+        &mut self,
+        operation: FileOperation,
+        cx: &mut Context<Self>,
+    ) -> Result<(), String> {
+        if matches!(operation, FileOperation::Create(_)) && self.is_dirty {
+            return Err("Save the current document before creating a new one".to_string());
+        }
+
+        // Resolve before the operation; the old path stops existing afterwards
+        let current = self.current_file.as_ref().map(|path| {
+            let path = std::path::PathBuf::from(path);
+            path.canonicalize().unwrap_or(path)
+        });
+
+        operation
+            .apply()
+            .map_err(|e| format!("Could not {}: {}", operation, e))?;
+        self.file_tree.operation_applied(&operation);
+
+        match operation {
+            FileOperation::Create(path) => {
+                self.load_file(path, cx);
+            }
+            FileOperation::CreateDir(_) | FileOperation::Duplicate { .. } => {}
+            FileOperation::Move { from, to } => {
+                self.open_history.rename(&from, &to);
+                if let Some(moved) = current.and_then(|current| relocate(&current, &from, &to)) {
+                    self.folds.rename(&moved);
+                    self.current_file = Some(moved.to_string_lossy().to_string());
+                }
+            }
+            FileOperation::Trash(path) => {
+                if current.is_some_and(|current| current.starts_with(&path)) {
+                    self.current_file = None;
+                    self.is_dirty = true;
+                }
+            }
+        }
+        cx.notify();
+        Ok(())
+    }
+
+    /// Handles mouse down in the text and starts a mouse selection.
     ///
-    /// Hardcoded layout constants should be extracted to `TextEditor` constants:
-    /// - `char_width`: 7.2px (assumes monospace font)
-    /// - `line_height`: 22px
-    /// - `header_height`: 30px (status bar)
-    /// - `padding`: 16px
-    fn handle_mouse_down(&mut self, event: &MouseDownEvent, cx: &mut Context<Self>) {
-        self.clear_selection();
+    /// A click places the caret, Shift+click extends the selection from its
+    /// anchor, a double-click selects a word and a triple-click a line. Dragging
+    /// afterwards extends the selection by the same unit. Alt+click adds a caret
+    /// (or removes the one clicked) and keeps the others, and Alt+Shift+drag
+    /// selects a block.
+    fn handle_mouse_down(
+        &mut self,
+        event: &MouseDownEvent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let offset = self.offset_at_point(event.position, window);
+        let unit = SelectionUnit::from_click_count(event.click_count);
+
+        if event.modifiers.alt && event.modifiers.shift {
+            let position = self.position_at_point(event.position, window);
+            self.select_block(BlockSelection {
+                anchor: position,
+                head: position,
+            });
+            self.mouse_drag = Some(MouseDrag {
+                unit: SelectionUnit::Char,
+                origin: offset..offset,
+                column: true,
+                pointer: event.position,
+                _autoscroll: self.spawn_drag_autoscroll(window, cx),
+            });
+            cx.notify();
+            return;
+        }
 
-        let char_width = px(7.2);
-        let line_height = px(22.0);
-        let header_height = px(30.0);
-        let padding = px(16.0);
+        if event.modifiers.alt {
+            let before = self.extra_cursors.len();
+            self.extra_cursors
+                .retain(|cursor| *cursor != Cursor::at(offset));
+            if self.extra_cursors.len() != before {
+                cx.notify();
+                return;
+            }
+            self.extra_cursors.push(Cursor {
+                head: self.cursor_position,
+                anchor: self.selection_start,
+            });
+            self.selection_start = None;
+        } else {
+            self.clear_extra_cursors();
+        }
 
-        let click_x = event.position.x - padding;
-        let click_y = event.position.y - padding - header_height + px(self.scroll_offset);
+        let origin = if event.modifiers.shift && unit == SelectionUnit::Char {
+            let anchor = self.selection_start.unwrap_or(self.cursor_position);
+            anchor..anchor
+        } else {
+            selection::unit_range(&self.content, offset, unit)
+        };
+        self.mouse_drag = Some(MouseDrag {
+            unit,
+            origin,
+            column: false,
+            pointer: event.position,
+            _autoscroll: self.spawn_drag_autoscroll(window, cx),
+        });
+        self.extend_mouse_selection(offset);
+        cx.notify();
+    }
 
-        let clicked_line = ((click_y / line_height).max(0.0).floor() as usize).max(0);
+    /// Extends the mouse selection to the position under the pointer.
+    fn handle_mouse_drag(
+        &mut self,
+        position: Point<Pixels>,
+        window: &Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(drag) = self.mouse_drag.as_mut() else {
+            return;
+        };
+        drag.pointer = position;
+        self.drag_to(position, window);
+        cx.notify();
+    }
 
-        let clicked_col = ((click_x / char_width).max(0.0).round() as usize).max(0);
+    /// Ends the mouse selection and offers the selected text as the primary selection.
+    fn handle_mouse_up(&mut self, cx: &mut Context<Self>) {
+        if self.mouse_drag.take().is_none() {
+            return;
+        }
+        // An Alt+drag may have run into other carets
+        self.set_cursors(self.cursors());
+        if let Some(text) = self.get_selected_text() {
+            write_primary(text, cx);
+        }
+    }
 
-        let lines: Vec<&str> = self.content.split('\n').collect();
+    /// Handles middle-click by pasting the primary selection at the click.
+    fn handle_middle_click(
+        &mut self,
+        event: &MouseDownEvent,
+        window: &Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(text) = read_primary(cx) else {
+            return;
+        };
+        self.clear_selection();
+        self.clear_extra_cursors();
+        self.autocomplete = None;
+        self.cursor_position = self.offset_at_point(event.position, window);
+        self.insert_text(&text, cx);
+    }
 
-        let target_line = clicked_line.min(lines.len().saturating_sub(1));
+    /// Extends the mouse selection, or the block of an Alt+Shift drag, to `point`.
+    fn drag_to(&mut self, point: Point<Pixels>, window: &Window) {
+        let column = self.mouse_drag.as_ref().is_some_and(|drag| drag.column);
+        match self.block_selection.filter(|_| column) {
+            Some(block) => {
+                let head = self.position_at_point(point, window);
+                self.select_block(BlockSelection {
+                    anchor: block.anchor,
+                    head,
+                });
+            }
+            None => {
+                let offset = self.offset_at_point(point, window);
+                self.extend_mouse_selection(offset);
+            }
+        }
+    }
 
-        let mut byte_position = 0;
-        for (idx, line) in lines.iter().enumerate() {
-            if idx == target_line {
-                let target_col = clicked_col.min(line.len());
-                byte_position += target_col;
-                break;
+    /// Selects from the drag origin to the unit (character, word or line) at `offset`.
+    ///
+    /// Dragging before the origin anchors the selection at the origin's end, so a
+    /// double-clicked word stays selected whichever way the drag goes.
+    fn extend_mouse_selection(&mut self, offset: usize) {
+        let Some(drag) = &self.mouse_drag else {
+            return;
+        };
+        let target = selection::unit_range(&self.content, offset, drag.unit);
+        let (anchor, head) = if target.start < drag.origin.start {
+            (drag.origin.end, target.start)
+        } else {
+            (drag.origin.start, target.end.max(drag.origin.end))
+        };
+        self.selection_start = (anchor != head).then_some(anchor);
+        self.cursor_position = head;
+    }
+
+    /// Scrolls while a drag holds the pointer above or below the text, which
+    /// produces no mouse events of its own.
+    fn spawn_drag_autoscroll(&self, window: &Window, cx: &mut Context<Self>) -> Task<()> {
+        cx.spawn_in(window, async move |editor, cx| {
+            loop {
+                cx.background_executor().timer(AUTOSCROLL_INTERVAL).await;
+                let dragging =
+                    editor.update_in(cx, |editor, window, cx| editor.autoscroll_drag(window, cx));
+                if !matches!(dragging, Ok(true)) {
+                    break;
+                }
             }
-            byte_position += line.len() + 1;
+        })
+    }
+
+    /// One autoscroll step: scrolls faster the further the pointer is past the
+    /// edge and extends the selection to the rows scrolled into view.
+    ///
+    /// Returns false once the drag has ended.
+    fn autoscroll_drag(&mut self, window: &Window, cx: &mut Context<Self>) -> bool {
+        let Some(drag) = &self.mouse_drag else {
+            return false;
+        };
+        let pointer = drag.pointer;
+        let overshoot = if pointer.y < self.text_bounds.top() {
+            pointer.y - self.text_bounds.top()
+        } else if pointer.y > self.text_bounds.bottom() {
+            pointer.y - self.text_bounds.bottom()
+        } else {
+            return true;
+        };
+
+        let line_height = f32::from(self.text_metrics.line_height);
+        let step = (f32::from(overshoot) / 2.0).clamp(-4.0 * line_height, 4.0 * line_height);
+        let scroll_offset = (self.scroll_offset + step).clamp(0.0, self.max_scroll());
+        if scroll_offset != self.scroll_offset {
+            self.scroll_offset = scroll_offset;
+            self.drag_to(pointer, window);
+            cx.notify();
+        }
+        true
+    }
+
+    /// Largest scroll offset that still fills the viewport, in pixels.
+    fn max_scroll(&self) -> f32 {
+        let content_height =
+            self.visual_rows().len() as f32 * f32::from(self.text_metrics.line_height);
+        (content_height - f32::from(self.text_bounds.size.height)).max(0.0)
+    }
+
+    /// Document offset of the caret position closest to a point in the window.
+    ///
+    /// The row comes from the row height and the last laid-out text bounds; the
+    /// column from hit-testing the row's shaped text, so wide and combining
+    /// characters resolve to the glyph that was actually drawn there.
+    fn offset_at_point(&self, point: Point<Pixels>, window: &Window) -> usize {
+        let metrics = &self.text_metrics;
+        let y = point.y - self.text_bounds.origin.y + px(self.scroll_offset);
+        let rows = self.visual_rows();
+        let row_idx = ((y / metrics.line_height).max(0.0).floor() as usize).min(rows.len() - 1);
+        let row = rows[row_idx];
+
+        let x = point.x
+            - self.text_bounds.origin.x
+            - px(FOLD_GUTTER_WIDTH)
+            - metrics.char_width * row.indent as f32;
+        let text = &self.content[row.start..row.end];
+        let index = metrics.index_for_x(text, x, window);
+
+        // Rows that continue on the next row stop before the wrap point
+        let max_index = if row.last {
+            text.len()
+        } else {
+            text.char_indices().last().map_or(0, |(idx, _)| idx)
+        };
+        row.start + index.min(max_index)
+    }
+
+    /// Line and column under a point in the window, for block selection.
+    ///
+    /// Past the end of a line the column keeps counting in character widths, so
+    /// a block can be dragged wider than the lines it covers.
+    fn position_at_point(&self, point: Point<Pixels>, window: &Window) -> Position {
+        let offset = self.offset_at_point(point, window);
+        let position = Position::of(&self.content, offset);
+        let at_line_end = self.content[offset..].starts_with('\n') || offset == self.content.len();
+        if !at_line_end {
+            return position;
         }
 
-        self.cursor_position = byte_position;
-        cx.notify();
+        let metrics = &self.text_metrics;
+        let rows = self.visual_rows();
+        let row = rows[wrap::row_at(&rows, offset)];
+        let text = &self.content[row.start..row.end];
+        let end_x = self.text_bounds.origin.x
+            + px(FOLD_GUTTER_WIDTH)
+            + metrics.char_width * row.indent as f32
+            + metrics.x_for_index(text, text.len(), window);
+        let beyond = ((point.x - end_x) / metrics.char_width).round().max(0.0) as usize;
+        Position {
+            line: position.line,
+            column: position.column + beyond,
+        }
     }
 
     /// Handles mouse scroll wheel events for vertical scrolling.
     ///
     /// Supports both pixel-based and line-based scroll deltas.
-    /// Clamps scroll offset to valid range [0, max_content_height - viewport_height].
-    ///
-    /// # Magic Numbers
-    ///
-    /// - `line_height`: 22.0px (should match rendering constant)
-    /// - `viewport_height`: 538.0px (derived from window height - header - padding)
+    /// Clamps scroll offset to valid range [0, max_content_height - viewport_height],
+    /// where the viewport is the text area's size from the last layout.
     fn handle_scroll_wheel(&mut self, event: &ScrollWheelEvent, cx: &mut Context<Self>) {
-        let line_height = 22.0;
+        let line_height = f32::from(self.text_metrics.line_height);
 
         let scroll_amount = match event.delta {
             gpui::ScrollDelta::Pixels(delta) => delta.y.into(),
@@ -1159,14 +3701,7 @@ impl TextEditor {
         };
 
         self.scroll_offset -= scroll_amount;
-
-        let lines: Vec<&str> = self.content.split('\n').collect();
-        let total_content_height = lines.len() as f32 * line_height;
-
-        let viewport_height = 538.0;
-        let max_scroll = (total_content_height - viewport_height).max(0.0);
-
-        self.scroll_offset = self.scroll_offset.clamp(0.0, max_scroll);
+        self.scroll_offset = self.scroll_offset.clamp(0.0, self.max_scroll());
 
         cx.notify();
     }
@@ -1175,73 +3710,38 @@ impl TextEditor {
     ///
     /// Algorithm:
     /// 1. Find current line and column position
-    /// 2. Move to previous line
+    /// 2. Move to the previous visible line, skipping folded regions
     /// 3. Clamp column to line length (handles lines of different lengths)
     /// 4. Convert (line, column) back to byte offset
     ///
     /// This logic is shared by `handle_move_up` and `handle_select_up`.
     fn move_up_internal(&mut self) {
-        let lines: Vec<&str> = self.content.split('\n').collect();
-        let mut current_pos = 0;
-        let mut current_line = 0;
-        let mut col_in_line = 0;
-
-        for (line_idx, line) in lines.iter().enumerate() {
-            if current_pos + line.len() >= self.cursor_position {
-                current_line = line_idx;
-                col_in_line = self.cursor_position - current_pos;
-                break;
-            }
-            current_pos += line.len() + 1;
-        }
-
-        if current_line > 0 {
-            let prev_line_len = lines[current_line - 1].len();
-            let new_col = col_in_line.min(prev_line_len);
-            let mut new_pos = 0;
-            for (i, line) in lines.iter().enumerate() {
-                if i == current_line - 1 {
-                    new_pos += new_col;
-                    break;
-                }
-                new_pos += line.len() + 1;
-            }
-            self.cursor_position = new_pos;
-        }
+        self.move_vertically(-1);
     }
 
     /// Internal helper for moving cursor down one line while preserving column position.
     ///
-    /// Algorithm mirrors `move_up_internal` but moves to the next line instead.
+    /// Algorithm mirrors `move_up_internal` but moves to the next visible line instead.
     /// Handles edge cases like moving from long line to short line gracefully.
     fn move_down_internal(&mut self) {
-        let lines: Vec<&str> = self.content.split('\n').collect();
-        let mut current_pos = 0;
-        let mut current_line = 0;
-        let mut col_in_line = 0;
+        self.move_vertically(1);
+    }
 
-        for (line_idx, line) in lines.iter().enumerate() {
-            if current_pos + line.len() >= self.cursor_position {
-                current_line = line_idx;
-                col_in_line = self.cursor_position - current_pos;
-                break;
-            }
-            current_pos += line.len() + 1;
-        }
+    /// Moves the caret to the previous (`direction < 0`) or next screen row,
+    /// skipping folded regions and keeping the column on wrapped rows.
+    fn move_vertically(&mut self, direction: isize) {
+        self.cursor_position = self.offset_rows_away(self.cursor_position, direction);
+    }
 
-        if current_line < lines.len() - 1 {
-            let next_line_len = lines[current_line + 1].len();
-            let new_col = col_in_line.min(next_line_len);
-            let mut new_pos = 0;
-            for (i, line) in lines.iter().enumerate() {
-                if i == current_line + 1 {
-                    new_pos += new_col;
-                    break;
-                }
-                new_pos += line.len() + 1;
-            }
-            self.cursor_position = new_pos;
-        }
+    /// Offset `delta` screen rows above (negative) or below `from`, keeping its
+    /// column; clamped to the first and last row.
+    fn offset_rows_away(&self, from: usize, delta: isize) -> usize {
+        let rows = self.visual_rows();
+        let row = wrap::row_at(&rows, from);
+        let column = rows[row].indent + wrap::str_width(&self.content[rows[row].start..from]);
+
+        let target = row.saturating_add_signed(delta).min(rows.len() - 1);
+        self.offset_in_row(&rows[target], column)
     }
 }
 
@@ -1270,18 +3770,65 @@ impl Focusable for TextEditor {
 /// # Rendering Architecture
 ///
 /// - Uses GPUI's flexbox-based layout system
-/// - Cursor is rendered as a 4px wide colored div
+/// - Cursor is rendered as a 2px wide colored div that takes no horizontal space
 /// - Selection uses background color overlay
-/// - Text is rendered in monospace font for consistent character width
+/// - Rows have a fixed height from `TextMetrics`; clicks are hit-tested against shaped text
 impl Render for TextEditor {
     fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        self.text_metrics = TextMetrics::measure(window);
+
+        // Wrap at the configured column, or at the right edge of the text area.
+        // Before the first layout the width is estimated from the window.
+        let text_width = if self.text_bounds.size.width > px(0.0) {
+            f32::from(self.text_bounds.size.width)
+        } else {
+            let text_area_chrome = 2.0 * 16.0 + 2.0;
+            f32::from(window.viewport_size().width) - self.sidebar_width() - text_area_chrome
+        };
+        let window_columns =
+            ((text_width - FOLD_GUTTER_WIDTH) / f32::from(self.text_metrics.char_width)).floor();
+        self.wrap_width = self.soft_wrap.then(|| {
+            self.wrap_column
+                .unwrap_or(window_columns.max(0.0) as usize)
+                .max(wrap::MIN_WRAP_COLUMNS)
+        });
+
         // Check if palette wants to open a file or close
-        if let Some(palette_entity) = &self.palette {
+        if let Some(palette_entity) = self.palette.clone() {
             let palette = palette_entity.read(cx);
-            if palette.should_open {
+            if let Some(operation) = palette.operation.clone() {
+                let _ = palette;
+                match self.apply_file_operation(operation, cx) {
+                    Ok(()) => {
+                        self.palette = None;
+                        window.focus(&self.focus_handle);
+                    }
+                    // Keep the palette open so the reason is visible
+                    Err(message) => {
+                        palette_entity.update(cx, |palette, cx| palette.show_error(message, cx))
+                    }
+                }
+            } else if palette.should_open {
                 let selected_file = palette.get_selected_file();
+                let selected_command = palette.get_selected_command();
+                let selected_heading = palette.get_selected_heading();
+                let selected_workspace_heading = palette.get_selected_workspace_heading();
                 let _ = palette;
-                if let Some(file_to_load) = selected_file {
+                if let Some(action) = selected_command {
+                    // Run the command against the editor once it has focus again
+                    self.palette = None;
+                    window.focus(&self.focus_handle);
+                    window.dispatch_action(action, cx);
+                    cx.notify();
+                } else if let Some(offset) = selected_heading {
+                    self.palette = None;
+                    window.focus(&self.focus_handle);
+                    self.jump_to(offset, cx);
+                } else if let Some((path, line)) = selected_workspace_heading {
+                    self.palette = None;
+                    window.focus(&self.focus_handle);
+                    self.open_at_line(path, line, cx);
+                } else if let Some(file_to_load) = selected_file {
                     self.palette = None;
                     window.focus(&self.focus_handle);
                     self.load_file(file_to_load, cx);
@@ -1298,8 +3845,14 @@ impl Render for TextEditor {
             .track_focus(&self.focus_handle(cx))
             .on_mouse_down(
                 gpui::MouseButton::Left,
-                cx.listener(|editor, event: &MouseDownEvent, _, cx| {
-                    editor.handle_mouse_down(event, cx);
+                cx.listener(|editor, event: &MouseDownEvent, window, cx| {
+                    editor.handle_mouse_down(event, window, cx);
+                }),
+            )
+            .on_mouse_down(
+                gpui::MouseButton::Middle,
+                cx.listener(|editor, event: &MouseDownEvent, window, cx| {
+                    editor.handle_middle_click(event, window, cx);
                 }),
             )
             .on_scroll_wheel(cx.listener(|editor, event: &ScrollWheelEvent, _, cx| {
@@ -1310,6 +3863,29 @@ impl Render for TextEditor {
             .on_action(cx.listener(Self::handle_move_up))
             .on_action(cx.listener(Self::handle_move_down))
             .on_action(cx.listener(Self::handle_backspace))
+            .on_action(cx.listener(Self::handle_delete))
+            .on_action(cx.listener(Self::handle_delete_word_backward))
+            .on_action(cx.listener(Self::handle_delete_word_forward))
+            .on_action(cx.listener(Self::handle_delete_line))
+            .on_action(cx.listener(Self::handle_delete_to_line_end))
+            .on_action(cx.listener(Self::handle_add_next_occurrence))
+            .on_action(cx.listener(Self::handle_add_cursor_above))
+            .on_action(cx.listener(Self::handle_add_cursor_below))
+            .on_action(cx.listener(Self::handle_select_all_matches))
+            .on_action(cx.listener(Self::handle_column_select_up))
+            .on_action(cx.listener(Self::handle_column_select_down))
+            .on_action(cx.listener(Self::handle_column_select_left))
+            .on_action(cx.listener(Self::handle_column_select_right))
+            .on_action(cx.listener(Self::handle_indent_lines))
+            .on_action(cx.listener(Self::handle_outdent_lines))
+            .on_action(cx.listener(Self::handle_move_line_up))
+            .on_action(cx.listener(Self::handle_move_line_down))
+            .on_action(cx.listener(Self::handle_duplicate_line))
+            .on_action(cx.listener(Self::handle_join_lines))
+            .on_action(cx.listener(Self::handle_sort_lines))
+            .on_action(cx.listener(Self::handle_reverse_lines))
+            .on_action(cx.listener(Self::handle_unique_lines))
+            .on_action(cx.listener(Self::handle_transpose_chars))
             .on_action(cx.listener(Self::handle_enter))
             .on_action(cx.listener(Self::handle_save))
             .on_action(cx.listener(Self::handle_quit))
@@ -1325,6 +3901,39 @@ impl Render for TextEditor {
             .on_action(cx.listener(Self::handle_find_next))
             .on_action(cx.listener(Self::handle_find_previous))
             .on_action(cx.listener(Self::handle_toggle_palette))
+            .on_action(cx.listener(Self::handle_toggle_command_palette))
+            .on_action(cx.listener(Self::handle_go_to_symbol))
+            .on_action(cx.listener(Self::handle_toggle_outline))
+            .on_action(cx.listener(Self::handle_move_section_up))
+            .on_action(cx.listener(Self::handle_move_section_down))
+            .on_action(cx.listener(Self::handle_promote_section))
+            .on_action(cx.listener(Self::handle_demote_section))
+            .on_action(cx.listener(Self::handle_toggle_file_tree))
+            .on_action(cx.listener(Self::handle_fold))
+            .on_action(cx.listener(Self::handle_unfold))
+            .on_action(cx.listener(Self::handle_fold_all))
+            .on_action(cx.listener(Self::handle_unfold_all))
+            .on_action(cx.listener(Self::handle_move_home))
+            .on_action(cx.listener(Self::handle_move_end))
+            .on_action(cx.listener(Self::handle_toggle_soft_wrap))
+            .on_action(cx.listener(Self::handle_select_home))
+            .on_action(cx.listener(Self::handle_select_end))
+            .on_action(cx.listener(Self::handle_move_word_left))
+            .on_action(cx.listener(Self::handle_move_word_right))
+            .on_action(cx.listener(Self::handle_select_word_left))
+            .on_action(cx.listener(Self::handle_select_word_right))
+            .on_action(cx.listener(Self::handle_move_to_start))
+            .on_action(cx.listener(Self::handle_move_to_end))
+            .on_action(cx.listener(Self::handle_select_to_start))
+            .on_action(cx.listener(Self::handle_select_to_end))
+            .on_action(cx.listener(Self::handle_page_up))
+            .on_action(cx.listener(Self::handle_page_down))
+            .on_action(cx.listener(Self::handle_select_page_up))
+            .on_action(cx.listener(Self::handle_select_page_down))
+            .on_action(cx.listener(Self::handle_move_paragraph_up))
+            .on_action(cx.listener(Self::handle_move_paragraph_down))
+            .on_action(cx.listener(Self::handle_select_paragraph_up))
+            .on_action(cx.listener(Self::handle_select_paragraph_down))
             .on_key_down(cx.listener(|editor, event: &KeyDownEvent, _, cx| {
                 if editor.handle_find_key_event(event, cx) {
                     return;
@@ -1337,6 +3946,13 @@ impl Render for TextEditor {
                     return;
                 }
 
+                // Escape goes back to a single cursor
+                if event.keystroke.key == "escape" && !editor.extra_cursors.is_empty() {
+                    editor.clear_extra_cursors();
+                    cx.notify();
+                    return;
+                }
+
                 // Regular character input (only when palette is closed)
                 if editor.palette.is_none() && editor.find_panel.is_none() {
                     if let Some(key_char) = &event.keystroke.key_char {
@@ -1347,7 +3963,7 @@ impl Render for TextEditor {
                         {
                             if let Some(c) = key_char.chars().next() {
                                 if c.is_ascii_graphic() || c == ' ' {
-                                    editor.insert_char(c, cx);
+                                    editor.for_each_cursor(|editor, _| editor.insert_char(c, cx));
                                 }
                             }
                         }
@@ -1356,7 +3972,8 @@ impl Render for TextEditor {
             }))
             .flex()
             .flex_col()
-            .size_full()
+            .flex_1()
+            .h_full()
             .bg(rgb(0x2d2d2d))
             .border_1()
             .border_color(rgb(0x454545))
@@ -1364,8 +3981,8 @@ impl Render for TextEditor {
             .shadow_lg()
             .text_color(rgb(0xd4d4d4))
             .p_4()
-            .font_family("monospace")
-            .text_sm()
+            .font_family(EDITOR_FONT_FAMILY)
+            .text_size(EDITOR_FONT_SIZE)
             .child(div().mb_2().text_color(rgb(0x808080)).child(format!(
                         "MedleyText - {} | Ctrl+P: files | Ctrl+S: save | Ctrl+Q: quit",
                         self.current_file
@@ -1375,83 +3992,21 @@ impl Render for TextEditor {
                     )))
             .child(
                 div()
+                    .relative()
                     .flex()
                     .flex_col()
                     .gap_1()
                     .flex_1()
                     .overflow_hidden()
-                    .child(div().flex().flex_col().mt(px(-self.scroll_offset)).child({
-                        let lines: Vec<&str> = self.content.split('\n').collect();
-                        let mut current_pos = 0;
-                        let mut result = div().flex().flex_col();
-                        let selection_range = self.get_selection_range();
-
-                        for line in lines {
-                            let line_start = current_pos;
-                            let line_end = current_pos + line.len();
-                            let cursor_on_line = self.cursor_position >= line_start
-                                && self.cursor_position <= line_end;
-
-                            let tokens = MarkdownHighlighter::tokenize_line(line);
-
-                            let mut line_div = div().flex().flex_row().min_h(px(18.0));
-                            let mut char_count = 0;
-
-                            for (text, token_type) in tokens {
-                                let token_color = MarkdownHighlighter::get_color(&token_type);
-                                let token_start = line_start + char_count;
-                                let cursor_pos = if cursor_on_line {
-                                    Some(self.cursor_position)
-                                } else {
-                                    None
-                                };
-
-                                let segments = self.build_segments_for_token(
-                                    &text,
-                                    token_color,
-                                    token_start,
-                                    selection_range,
-                                    cursor_pos,
-                                    self.find_panel.as_ref(),
-                                );
-
-                                for segment in segments {
-                                    match segment {
-                                        SegmentPiece::Cursor => {
-                                            line_div = line_div.child(
-                                                div().w(px(4.0)).h(px(18.0)).bg(rgb(0xcccccc)),
-                                            );
-                                        }
-                                        SegmentPiece::Text(run) => {
-                                            if run.text.is_empty() {
-                                                continue;
-                                            }
-                                            let mut node = div().text_color(run.text_color);
-                                            if let Some(bg) = run.background {
-                                                node = node.bg(bg);
-                                            }
-                                            line_div = line_div.child(node.child(run.text));
-                                        }
-                                    }
-                                }
-
-                                char_count += text.len();
-                            }
-
-                            if cursor_on_line {
-                                let cursor_col = self.cursor_position - line_start;
-                                if cursor_col == line.len() {
-                                    line_div = line_div
-                                        .child(div().w(px(4.0)).h(px(18.0)).bg(rgb(0xcccccc)));
-                                }
-                            }
-
-                            result = result.child(line_div);
-                            current_pos = line_end + 1;
-                        }
-
-                        result
-                    })),
+                    .child(self.track_text_viewport(cx))
+                    .child(
+                        div()
+                            .flex()
+                            .flex_col()
+                            .mt(px(-self.scroll_offset))
+                            .line_height(self.text_metrics.line_height)
+                            .child(self.render_text_rows(cx)),
+                    ),
             )
             .child(
                 div()
@@ -1473,21 +4028,20 @@ impl Render for TextEditor {
             );
 
         // Wrap in a container and add overlays (autocomplete and/or palette)
-        let mut container = div().size_full().child(editor_content);
+        let mut container = div()
+            .size_full()
+            .flex()
+            .flex_row()
+            .when(self.file_tree.visible, |container| {
+                container.child(self.render_file_tree(window, cx))
+            })
+            .when(self.show_outline, |container| {
+                container.child(self.render_outline(cx))
+            })
+            .child(editor_content);
 
         if let Some(find_panel) = &self.find_panel {
-            let build_row = |label: &str, value: &str, placeholder: &str, active: bool| {
-                let display = if value.is_empty() {
-                    placeholder.to_string()
-                } else {
-                    value.to_string()
-                };
-                let text_color = if value.is_empty() {
-                    rgb(0x707070)
-                } else {
-                    rgb(0xffffff)
-                };
-
+            let build_row = |label: &str, input: &TextInput, placeholder: &str, active: bool| {
                 div()
                     .px_3()
                     .py_2()
@@ -1503,11 +4057,10 @@ impl Render for TextEditor {
                             .child(label.to_string()),
                     )
                     .child(
-                        div()
+                        input
+                            .render(placeholder, active)
                             .text_sm()
-                            .font_family("monospace")
-                            .text_color(text_color)
-                            .child(display),
+                            .font_family("monospace"),
                     )
             };
 
@@ -1559,7 +4112,7 @@ impl Render for TextEditor {
                         .text_xs()
                         .text_color(rgb(0x808080))
                         .child(
-                            "Enter: next • Shift+Enter: prev • Ctrl+R: replace • Ctrl+Shift+R: replace all • Esc: close"
+                            "Enter: next • Shift+Enter: prev • ↑↓: history • Ctrl+R: replace • Ctrl+Shift+R: replace all • Esc: close"
                                 .to_string(),
                         ),
                 );
@@ -1571,18 +4124,27 @@ impl Render for TextEditor {
         if let Some(autocomplete) = &self.autocomplete {
             let suggestions = autocomplete.get_suggestions_display();
 
-            // Calculate cursor position for positioning the dropdown
-            let line_height = 22.0;
-            let header_height = 30.0;
-            let padding = 16.0;
-            let current_line = self.get_current_line_number() as f32 - 1.0;
-            let top = padding + header_height + (current_line * line_height) + line_height
-                - self.scroll_offset;
+            // Place the dropdown below the caret
+            let metrics = &self.text_metrics;
+            let rows = self.visual_rows();
+            let row_idx = wrap::row_at(&rows, self.cursor_position);
+            let row = rows[row_idx];
+            let caret_x = metrics.x_for_index(
+                &self.content[row.start..row.end],
+                self.cursor_position.max(row.start) - row.start,
+                window,
+            );
+            let top = self.text_bounds.origin.y + metrics.line_height * (row_idx + 1) as f32
+                - px(self.scroll_offset);
+            let left = self.text_bounds.origin.x
+                + px(FOLD_GUTTER_WIDTH)
+                + metrics.char_width * row.indent as f32
+                + caret_x;
 
             let autocomplete_menu = div()
                 .absolute()
-                .top(px(top))
-                .left(px(padding))
+                .top(top)
+                .left(left)
                 .w(px(400.0))
                 .bg(rgb(0x2d2d2d))
                 .border_1()
@@ -1630,3 +4192,23 @@ impl Render for TextEditor {
         container
     }
 }
+
+/// Text of the X11/Wayland primary selection, on platforms that have one.
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+fn read_primary(cx: &App) -> Option<String> {
+    cx.read_from_primary()?.text()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "freebsd")))]
+fn read_primary(_: &App) -> Option<String> {
+    None
+}
+
+/// Offers `text` as the primary selection, on platforms that have one.
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+fn write_primary(text: String, cx: &mut App) {
+    cx.write_to_primary(ClipboardItem::new_string(text));
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "freebsd")))]
+fn write_primary(_: String, _: &mut App) {}
@@ -0,0 +1,266 @@
+//! File operations offered by the palette and the file tree: create, move,
+//! duplicate and delete.
+//!
+//! Deleting never unlinks a document; it is moved to the freedesktop.org trash
+//! (`$XDG_DATA_HOME/Trash`, falling back to `~/.local/share/Trash`) together
+//! with a `.trashinfo` record, so file managers can restore it.
+
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::{self, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A file system change requested from the palette or the file tree.
+#[derive(Clone, Debug)]
+pub enum FileOperation {
+    /// Create an empty file, including missing parent directories.
+    Create(PathBuf),
+    /// Create a directory, including missing parents.
+    CreateDir(PathBuf),
+    /// Rename or move a file or directory; the destination must not exist.
+    Move { from: PathBuf, to: PathBuf },
+    /// Copy a file to a new path; the destination must not exist.
+    Duplicate { from: PathBuf, to: PathBuf },
+    /// Move a file or directory to the trash.
+    Trash(PathBuf),
+}
+
+impl FileOperation {
+    /// Performs the operation on disk.
+    pub fn apply(&self) -> io::Result<()> {
+        match self {
+            FileOperation::Create(path) => {
+                create_parent_dirs(path)?;
+                OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .open(path)
+                    .map(|_| ())
+            }
+            FileOperation::CreateDir(path) => {
+                ensure_vacant(path)?;
+                std::fs::create_dir_all(path)
+            }
+            FileOperation::Move { from, to } => {
+                ensure_vacant(to)?;
+                create_parent_dirs(to)?;
+                move_file(from, to)
+            }
+            FileOperation::Duplicate { from, to } => {
+                ensure_vacant(to)?;
+                create_parent_dirs(to)?;
+                std::fs::copy(from, to).map(|_| ())
+            }
+            FileOperation::Trash(path) => trash(path),
+        }
+    }
+}
+
+impl fmt::Display for FileOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FileOperation::Create(path) => write!(f, "create {}", path.display()),
+            FileOperation::CreateDir(path) => write!(f, "create folder {}", path.display()),
+            FileOperation::Move { from, to } => {
+                write!(f, "move {} to {}", from.display(), to.display())
+            }
+            FileOperation::Duplicate { from, to } => {
+                write!(f, "duplicate {} to {}", from.display(), to.display())
+            }
+            FileOperation::Trash(path) => write!(f, "move {} to the trash", path.display()),
+        }
+    }
+}
+
+/// Returns where `path` ends up after `from` was moved to `to`, or `None` when
+/// `path` is neither `from` nor inside it.
+pub fn relocate(path: &Path, from: &Path, to: &Path) -> Option<PathBuf> {
+    let rest = path.strip_prefix(from).ok()?;
+    if rest.as_os_str().is_empty() {
+        Some(to.to_path_buf())
+    } else {
+        Some(to.join(rest))
+    }
+}
+
+fn create_parent_dirs(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => std::fs::create_dir_all(parent),
+        _ => Ok(()),
+    }
+}
+
+/// Refuses to overwrite an existing file.
+fn ensure_vacant(path: &Path) -> io::Result<()> {
+    if path.exists() {
+        return Err(io::Error::new(
+            ErrorKind::AlreadyExists,
+            format!("{} already exists", path.display()),
+        ));
+    }
+    Ok(())
+}
+
+/// Renames a file or directory, falling back to copy and delete across file
+/// systems. A directory is copied recursively; if that fails part way, the
+/// partial copy is removed and the original is left in place.
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    match std::fs::rename(from, to) {
+        Err(e) if e.kind() == ErrorKind::CrossesDevices => {
+            if std::fs::symlink_metadata(from)?.is_dir() {
+                if let Err(e) = copy_dir(from, to) {
+                    let _ = std::fs::remove_dir_all(to);
+                    return Err(e);
+                }
+                std::fs::remove_dir_all(from)
+            } else {
+                std::fs::copy(from, to)?;
+                std::fs::remove_file(from)
+            }
+        }
+        result => result,
+    }
+}
+
+/// Copies a directory tree; symbolic links inside it are not supported.
+fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    std::fs::create_dir(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let target = to.join(entry.file_name());
+        if file_type.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else if file_type.is_file() {
+            std::fs::copy(entry.path(), &target)?;
+        } else {
+            return Err(io::Error::new(
+                ErrorKind::Unsupported,
+                format!("cannot move {} across file systems", entry.path().display()),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the user's trash directory.
+fn trash_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_DATA_HOME")
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share"))
+        })?;
+    Some(base.join("Trash"))
+}
+
+/// Moves `path` into the trash and records where it came from.
+fn trash(path: &Path) -> io::Result<()> {
+    let trash = trash_dir()
+        .ok_or_else(|| io::Error::new(ErrorKind::NotFound, "no trash directory available"))?;
+    let files_dir = trash.join("files");
+    let info_dir = trash.join("info");
+    std::fs::create_dir_all(&files_dir)?;
+    std::fs::create_dir_all(&info_dir)?;
+
+    let original = path.canonicalize()?;
+    let name = original
+        .file_name()
+        .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "path has no file name"))?
+        .to_string_lossy()
+        .to_string();
+
+    // Claim a unique name by creating the info file first, as the spec requires
+    let mut attempt = 1;
+    let (trashed_name, mut info) = loop {
+        let candidate = numbered_name(&name, attempt);
+        let info_path = info_dir.join(format!("{}.trashinfo", candidate));
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&info_path)
+        {
+            Ok(file) if !files_dir.join(&candidate).exists() => break (candidate, file),
+            Ok(_) => {
+                let _ = std::fs::remove_file(&info_path);
+            }
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(e),
+        }
+        attempt += 1;
+    };
+    let info_path = info_dir.join(format!("{}.trashinfo", trashed_name));
+
+    let record = format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        percent_encode(&original.to_string_lossy()),
+        deletion_date()
+    );
+    if let Err(e) = info.write_all(record.as_bytes()) {
+        let _ = std::fs::remove_file(&info_path);
+        return Err(e);
+    }
+
+    move_file(&original, &files_dir.join(&trashed_name)).inspect_err(|_| {
+        let _ = std::fs::remove_file(&info_path);
+    })
+}
+
+/// Returns `name` for the first attempt and `name 2.ext`, `name 3.ext`, ... afterwards.
+fn numbered_name(name: &str, attempt: usize) -> String {
+    if attempt == 1 {
+        return name.to_string();
+    }
+    match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => format!("{} {}.{}", stem, attempt, ext),
+        _ => format!("{} {}", name, attempt),
+    }
+}
+
+/// Percent-encodes a path for the `Path=` key, keeping `/` and unreserved characters.
+fn percent_encode(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Current time as `YYYY-MM-DDThh:mm:ss`.
+///
+/// The spec asks for local time; without a time zone database this uses UTC,
+/// which only affects the date shown by file managers.
+fn deletion_date() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (days, time) = (secs / 86_400, secs % 86_400);
+
+    // Civil date from days since the epoch (Howard Hinnant's algorithm)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        time / 3_600,
+        time % 3_600 / 60,
+        time % 60
+    )
+}
@@ -0,0 +1,424 @@
+//! File tree sidebar: a browsable view of the working directory.
+//!
+//! The rows are derived from the workspace index on every render, so folders
+//! and documents created, renamed or deleted outside the editor show up as soon
+//! as the index notices. Only expansion, selection and an in-progress name or
+//! delete confirmation prompt are kept here; the editor renders the tree and applies the file
+//! operations it produces, like it does for the palette.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use gpui::{App, FocusHandle, KeyDownEvent};
+
+use crate::file_ops::{FileOperation, relocate};
+use crate::palette::DEFAULT_NEW_FILE_EXTENSION;
+use crate::text_input::TextInput;
+use crate::workspace::WorkspaceFile;
+
+/// Width of the file tree sidebar in pixels.
+pub const FILE_TREE_WIDTH: f32 = 240.0;
+
+/// One visible line of the tree.
+#[derive(Clone, Debug)]
+pub struct TreeRow {
+    pub path: PathBuf,
+    /// File or folder name
+    pub name: String,
+    /// Nesting level below the workspace root, starting at 0
+    pub depth: usize,
+    pub is_dir: bool,
+    /// True for directories whose children are shown
+    pub expanded: bool,
+}
+
+/// Context actions offered for a row.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TreeAction {
+    NewFile,
+    NewFolder,
+    Rename,
+    Delete,
+}
+
+impl TreeAction {
+    /// Actions in context menu order.
+    pub const ALL: [TreeAction; 4] = [
+        TreeAction::NewFile,
+        TreeAction::NewFolder,
+        TreeAction::Rename,
+        TreeAction::Delete,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TreeAction::NewFile => "New File",
+            TreeAction::NewFolder => "New Folder",
+            TreeAction::Rename => "Rename",
+            TreeAction::Delete => "Delete (move to trash)",
+        }
+    }
+
+    /// Shortcut shown next to the label while the tree has focus.
+    pub fn keys(&self) -> &'static str {
+        match self {
+            TreeAction::NewFile => "Ctrl+N",
+            TreeAction::NewFolder => "Ctrl+Shift+N",
+            TreeAction::Rename => "F2",
+            TreeAction::Delete => "Delete",
+        }
+    }
+}
+
+/// Name input for creating or renaming an entry, or confirmation of a delete.
+#[derive(Clone, Debug)]
+pub struct TreePrompt {
+    pub action: TreeAction,
+    /// Directory to create in, or the entry being renamed or deleted
+    pub target: PathBuf,
+    pub input: TextInput,
+}
+
+/// What the editor should do after the tree handled a key or action.
+pub enum TreeOutcome {
+    /// The key is not used by the tree.
+    Ignored,
+    /// The tree changed its own state.
+    Handled,
+    /// Open this file in the editor.
+    Open(PathBuf),
+    /// Apply this change on disk.
+    Operation(FileOperation),
+    /// Return focus to the text.
+    Leave,
+}
+
+/// Expansion, selection and prompt state of the file tree sidebar.
+pub struct FileTreeState {
+    /// Whether the sidebar is shown.
+    pub visible: bool,
+    /// Focus handle for keyboard navigation inside the tree.
+    pub focus_handle: FocusHandle,
+    /// Canonical workspace root; its children are the top level of the tree
+    root: PathBuf,
+    /// Directories whose children are shown
+    expanded: HashSet<PathBuf>,
+    /// Highlighted entry, if any
+    pub selected: Option<PathBuf>,
+    /// Entry whose context menu is open
+    pub context_menu: Option<PathBuf>,
+    /// Name input for a pending create or rename, or a pending delete to confirm
+    pub prompt: Option<TreePrompt>,
+    /// Why the last operation failed, shown until the next key
+    pub error: Option<String>,
+}
+
+impl FileTreeState {
+    /// Creates a hidden, fully collapsed tree for `root`.
+    pub fn new(root: PathBuf, focus_handle: FocusHandle) -> Self {
+        Self {
+            visible: false,
+            focus_handle,
+            root,
+            expanded: HashSet::new(),
+            selected: None,
+            context_menu: None,
+            prompt: None,
+            error: None,
+        }
+    }
+
+    /// Canonical path of the directory shown by the tree.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Flattens the expanded part of the tree into rows, folders first.
+    pub fn visible_rows(&self, dirs: &[PathBuf], files: &[WorkspaceFile]) -> Vec<TreeRow> {
+        let is_open = |dir: &Path| dir == self.root.as_path() || self.expanded.contains(dir);
+
+        // Only entries whose parent is open can ever become visible
+        let mut children: HashMap<&Path, Vec<(&Path, bool)>> = HashMap::new();
+        let entries = dirs
+            .iter()
+            .map(|dir| (dir.as_path(), true))
+            .chain(files.iter().map(|file| (file.path.as_path(), false)));
+        for (path, is_dir) in entries {
+            match path.parent() {
+                Some(parent) if is_open(parent) => {
+                    children.entry(parent).or_default().push((path, is_dir))
+                }
+                _ => {}
+            }
+        }
+        for entries in children.values_mut() {
+            entries
+                .sort_by_cached_key(|&(path, is_dir)| (!is_dir, entry_name(path).to_lowercase()));
+        }
+
+        let mut rows = Vec::new();
+        self.push_children(&self.root, 0, &children, &mut rows);
+        rows
+    }
+
+    fn push_children(
+        &self,
+        dir: &Path,
+        depth: usize,
+        children: &HashMap<&Path, Vec<(&Path, bool)>>,
+        rows: &mut Vec<TreeRow>,
+    ) {
+        let Some(entries) = children.get(dir) else {
+            return;
+        };
+        for &(path, is_dir) in entries {
+            let expanded = is_dir && self.expanded.contains(path);
+            rows.push(TreeRow {
+                path: path.to_path_buf(),
+                name: entry_name(path),
+                depth,
+                is_dir,
+                expanded,
+            });
+            if expanded {
+                self.push_children(path, depth + 1, children, rows);
+            }
+        }
+    }
+
+    /// Expands or collapses a directory.
+    pub fn toggle(&mut self, dir: &Path) {
+        if !self.expanded.remove(dir) {
+            self.expanded.insert(dir.to_path_buf());
+        }
+    }
+
+    /// Expands every ancestor of `path` and selects it.
+    pub fn reveal(&mut self, path: &Path) {
+        let mut ancestor = path.parent();
+        while let Some(dir) = ancestor {
+            if !dir.starts_with(&self.root) || dir == self.root.as_path() {
+                break;
+            }
+            self.expanded.insert(dir.to_path_buf());
+            ancestor = dir.parent();
+        }
+        self.selected = Some(path.to_path_buf());
+    }
+
+    /// Handles a key while the tree has focus.
+    pub fn handle_key(
+        &mut self,
+        event: &KeyDownEvent,
+        rows: &[TreeRow],
+        cx: &mut App,
+    ) -> TreeOutcome {
+        self.error = None;
+        if let Some(prompt) = &mut self.prompt {
+            match event.keystroke.key.as_str() {
+                "escape" => self.prompt = None,
+                "enter" => {
+                    if let Some(operation) = self.confirm_prompt() {
+                        return TreeOutcome::Operation(operation);
+                    }
+                }
+                _ if prompt.action == TreeAction::Delete => {}
+                _ => {
+                    prompt.input.handle_key_down(event, cx);
+                }
+            }
+            return TreeOutcome::Handled;
+        }
+
+        let modifiers = &event.keystroke.modifiers;
+        if modifiers.alt || modifiers.platform {
+            return TreeOutcome::Ignored;
+        }
+        let position = self
+            .selected
+            .as_ref()
+            .and_then(|selected| rows.iter().position(|row| &row.path == selected));
+        let row = position.map(|position| &rows[position]);
+
+        match (
+            event.keystroke.key.as_str(),
+            modifiers.control,
+            modifiers.shift,
+        ) {
+            ("escape", false, false) => {
+                if self.context_menu.take().is_none() {
+                    return TreeOutcome::Leave;
+                }
+            }
+            ("up", false, false) => {
+                let previous = match position {
+                    Some(position) => position.saturating_sub(1),
+                    None => rows.len().saturating_sub(1),
+                };
+                self.select_row(rows, previous);
+            }
+            ("down", false, false) => {
+                let next = position.map_or(0, |position| position + 1);
+                self.select_row(rows, next.min(rows.len().saturating_sub(1)));
+            }
+            ("home", false, false) => self.select_row(rows, 0),
+            ("end", false, false) => self.select_row(rows, rows.len().saturating_sub(1)),
+            ("left", false, false) => match row {
+                Some(row) if row.expanded => self.toggle(&row.path),
+                // Jump to the parent folder
+                Some(row) => {
+                    let parent = row.path.parent().map(Path::to_path_buf);
+                    if let Some(parent) = parent.filter(|parent| parent != &self.root) {
+                        self.selected = Some(parent);
+                    }
+                }
+                None => {}
+            },
+            ("right", false, false) => match (row, position) {
+                (Some(row), _) if row.is_dir && !row.expanded => self.toggle(&row.path),
+                // Step into an expanded folder
+                (Some(row), Some(position))
+                    if row.expanded
+                        && rows
+                            .get(position + 1)
+                            .is_some_and(|next| next.depth > row.depth) =>
+                {
+                    self.select_row(rows, position + 1);
+                }
+                _ => {}
+            },
+            ("enter", false, false) => match row {
+                Some(row) if row.is_dir => self.toggle(&row.path),
+                Some(row) => return TreeOutcome::Open(row.path.clone()),
+                None => {}
+            },
+            ("n", true, false) => return self.begin(TreeAction::NewFile),
+            ("n", true, true) => return self.begin(TreeAction::NewFolder),
+            ("f2", false, false) => return self.begin(TreeAction::Rename),
+            ("delete", false, false) => return self.begin(TreeAction::Delete),
+            _ => return TreeOutcome::Ignored,
+        }
+        TreeOutcome::Handled
+    }
+
+    fn select_row(&mut self, rows: &[TreeRow], index: usize) {
+        if let Some(row) = rows.get(index) {
+            self.selected = Some(row.path.clone());
+        }
+    }
+
+    /// Starts a context action on the selected entry, or on the root when nothing is selected.
+    ///
+    /// Creating and renaming open a name prompt; deleting asks for confirmation
+    /// before the entry is moved to the trash.
+    pub fn begin(&mut self, action: TreeAction) -> TreeOutcome {
+        self.context_menu = None;
+        let selected = self.selected.clone();
+
+        match action {
+            TreeAction::NewFile | TreeAction::NewFolder => {
+                // New entries go into the selected folder, or next to the selected file
+                let dir = match selected {
+                    Some(path) if path.is_dir() => path,
+                    Some(path) => path.parent().unwrap_or(&self.root).to_path_buf(),
+                    None => self.root.clone(),
+                };
+                if dir != self.root {
+                    self.expanded.insert(dir.clone());
+                }
+                self.prompt = Some(TreePrompt {
+                    action,
+                    target: dir,
+                    input: TextInput::default(),
+                });
+            }
+            TreeAction::Rename => {
+                let Some(path) = selected.filter(|path| path != &self.root) else {
+                    return TreeOutcome::Handled;
+                };
+                let name = entry_name(&path);
+                self.prompt = Some(TreePrompt {
+                    action,
+                    target: path,
+                    input: TextInput::new(name),
+                });
+            }
+            TreeAction::Delete => {
+                let Some(path) = selected.filter(|path| path != &self.root) else {
+                    return TreeOutcome::Handled;
+                };
+                self.prompt = Some(TreePrompt {
+                    action,
+                    target: path,
+                    input: TextInput::default(),
+                });
+            }
+        }
+        TreeOutcome::Handled
+    }
+
+    /// Turns the typed name, or a confirmed delete, into an operation and
+    /// closes the prompt.
+    fn confirm_prompt(&mut self) -> Option<FileOperation> {
+        let prompt = self.prompt.as_ref()?;
+        let name = prompt.input.text().trim();
+        if name.is_empty() && prompt.action != TreeAction::Delete {
+            return None;
+        }
+
+        let operation = match prompt.action {
+            TreeAction::NewFile => {
+                let mut path = prompt.target.join(name);
+                if path.extension().is_none() {
+                    path.set_extension(DEFAULT_NEW_FILE_EXTENSION);
+                }
+                Some(FileOperation::Create(path))
+            }
+            TreeAction::NewFolder => Some(FileOperation::CreateDir(prompt.target.join(name))),
+            TreeAction::Rename => {
+                let from = prompt.target.clone();
+                let to = from.parent().unwrap_or(&self.root).join(name);
+                (to != from).then_some(FileOperation::Move { from, to })
+            }
+            TreeAction::Delete => Some(FileOperation::Trash(prompt.target.clone())),
+        };
+        self.prompt = None;
+        operation
+    }
+
+    /// Keeps expansion and selection in step with an operation that succeeded.
+    pub fn operation_applied(&mut self, operation: &FileOperation) {
+        match operation {
+            FileOperation::Create(path) | FileOperation::CreateDir(path) => self.reveal(path),
+            FileOperation::Duplicate { to, .. } => self.reveal(to),
+            FileOperation::Move { from, to } => {
+                self.expanded = self
+                    .expanded
+                    .drain()
+                    .map(|dir| relocate(&dir, from, to).unwrap_or(dir))
+                    .collect();
+                self.reveal(to);
+            }
+            FileOperation::Trash(path) => {
+                self.expanded.retain(|dir| !dir.starts_with(path));
+                if self
+                    .selected
+                    .as_ref()
+                    .is_some_and(|selected| selected.starts_with(path))
+                {
+                    self.selected = path
+                        .parent()
+                        .filter(|parent| *parent != self.root.as_path())
+                        .map(Path::to_path_buf);
+                }
+            }
+        }
+    }
+}
+
+/// Last path component, as shown in the tree.
+fn entry_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
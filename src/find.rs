@@ -2,6 +2,10 @@
 //!
 //! Keeps all search logic self-contained so the editor can focus on UI wiring.
 
+use gpui::App;
+
+use crate::text_input::{InputCommand, InputResponse, TextInput};
+
 /// Byte range of a search hit within the buffer.
 #[derive(Clone, Copy, Debug)]
 pub struct SearchMatch {
@@ -16,30 +20,101 @@ pub enum ActiveInput {
     Replace,
 }
 
+/// Maximum number of entries kept per history list.
+const HISTORY_LIMIT: usize = 50;
+
+/// State file holding previously used search queries.
+const QUERY_HISTORY_FILE: &str = "find_queries";
+
+/// State file holding previously used replacement strings.
+const REPLACE_HISTORY_FILE: &str = "find_replacements";
+
+/// Previously used queries and replacements, oldest first.
+///
+/// Lives on the editor rather than the panel so it survives closing the panel,
+/// and is mirrored to the state directory so it survives restarts.
+pub struct SearchHistory {
+    queries: Vec<String>,
+    replacements: Vec<String>,
+}
+
+impl SearchHistory {
+    /// Loads history from the state directory.
+    pub fn load() -> Self {
+        Self {
+            queries: crate::state::read_lines(QUERY_HISTORY_FILE),
+            replacements: crate::state::read_lines(REPLACE_HISTORY_FILE),
+        }
+    }
+
+    /// Entries for the given input row, oldest first.
+    pub fn entries(&self, input: ActiveInput) -> &[String] {
+        match input {
+            ActiveInput::Query => &self.queries,
+            ActiveInput::Replace => &self.replacements,
+        }
+    }
+
+    /// Records the panel's current query (and replacement, when visible) and persists them.
+    pub fn record(&mut self, panel: &FindPanelState) {
+        if Self::push_entry(&mut self.queries, panel.query.text()) {
+            crate::state::write_lines(QUERY_HISTORY_FILE, &self.queries);
+        }
+
+        if panel.show_replace && Self::push_entry(&mut self.replacements, panel.replace.text()) {
+            crate::state::write_lines(REPLACE_HISTORY_FILE, &self.replacements);
+        }
+    }
+
+    /// Appends an entry, moving duplicates to the end and trimming old items.
+    fn push_entry(list: &mut Vec<String>, value: &str) -> bool {
+        if value.is_empty() || value.contains('\n') {
+            return false;
+        }
+        if list.last().map(|last| last == value).unwrap_or(false) {
+            return false;
+        }
+
+        list.retain(|entry| entry != value);
+        list.push(value.to_string());
+        if list.len() > HISTORY_LIMIT {
+            let excess = list.len() - HISTORY_LIMIT;
+            list.drain(..excess);
+        }
+        true
+    }
+}
+
 /// Runtime state for the find/replace palette.
 pub struct FindPanelState {
-    pub query: String,
-    pub replace: String,
+    pub query: TextInput,
+    pub replace: TextInput,
     pub matches: Vec<SearchMatch>,
     pub selected_index: usize,
     pub show_replace: bool,
     pub active_input: ActiveInput,
     last_anchor: Option<usize>,
+    /// Position inside the history list while browsing with Up/Down.
+    history_index: Option<usize>,
+    /// Text typed before history browsing started, restored when moving past the newest entry.
+    history_draft: String,
 }
 
 impl FindPanelState {
     /// Creates a panel with an optional initial query (for example, the current selection).
     pub fn new(initial_query: Option<String>) -> Self {
-        let query = initial_query.unwrap_or_default();
+        let query = TextInput::new(initial_query.unwrap_or_default());
 
         Self {
             query,
-            replace: String::new(),
+            replace: TextInput::default(),
             matches: Vec::new(),
             selected_index: 0,
             show_replace: false,
             active_input: ActiveInput::Query,
             last_anchor: None,
+            history_index: None,
+            history_draft: String::new(),
         }
     }
 
@@ -67,30 +142,24 @@ impl FindPanelState {
         }
     }
 
-    /// Adds a typed character to the active input field.
-    pub fn push_char(&mut self, c: char, content: &str) {
-        match self.active_input {
-            ActiveInput::Query => {
-                self.query.push(c);
+    /// Applies an editing command to the active input field.
+    ///
+    /// Query edits rebuild the match list against `content`.
+    pub fn apply_input(
+        &mut self,
+        command: InputCommand,
+        content: &str,
+        cx: &mut App,
+    ) -> InputResponse {
+        let response = self.active_field_mut().apply(command, cx);
+        if response == InputResponse::Edited {
+            self.history_index = None;
+            if self.active_input == ActiveInput::Query {
                 self.last_anchor = None;
                 self.recompute_matches(content);
             }
-            ActiveInput::Replace => self.replace.push(c),
-        }
-    }
-
-    /// Deletes the last character from the active input.
-    pub fn backspace(&mut self, content: &str) {
-        match self.active_input {
-            ActiveInput::Query => {
-                self.query.pop();
-                self.last_anchor = None;
-                self.recompute_matches(content);
-            }
-            ActiveInput::Replace => {
-                self.replace.pop();
-            }
         }
+        response
     }
 
     /// Toggles the replace row visibility.
@@ -106,6 +175,59 @@ impl FindPanelState {
     /// Sets which row receives keyboard input.
     pub fn set_active_input(&mut self, input: ActiveInput) {
         self.active_input = input;
+        self.history_index = None;
+    }
+
+    /// Steps through history for the active row.
+    ///
+    /// A negative `direction` recalls older entries, a positive one newer entries.
+    /// Moving past the newest entry restores whatever was typed before browsing.
+    /// Returns `true` when the active field changed.
+    pub fn recall_history(&mut self, entries: &[String], direction: isize, content: &str) -> bool {
+        if entries.is_empty() {
+            return false;
+        }
+
+        let next = match (self.history_index, direction < 0) {
+            (None, true) => {
+                self.history_draft = self.active_value().to_string();
+                Some(entries.len() - 1)
+            }
+            (None, false) => return false,
+            (Some(0), true) => return false,
+            (Some(idx), true) => Some(idx - 1),
+            (Some(idx), false) if idx + 1 < entries.len() => Some(idx + 1),
+            (Some(_), false) => None,
+        };
+
+        let value = match next {
+            Some(idx) => entries[idx].clone(),
+            None => std::mem::take(&mut self.history_draft),
+        };
+        self.history_index = next;
+        self.active_field_mut().set_text(value);
+
+        if self.active_input == ActiveInput::Query {
+            self.last_anchor = None;
+            self.recompute_matches(content);
+        }
+        true
+    }
+
+    /// Text of the row currently receiving input.
+    fn active_value(&self) -> &str {
+        match self.active_input {
+            ActiveInput::Query => self.query.text(),
+            ActiveInput::Replace => self.replace.text(),
+        }
+    }
+
+    /// Input field of the row currently receiving input.
+    fn active_field_mut(&mut self) -> &mut TextInput {
+        match self.active_input {
+            ActiveInput::Query => &mut self.query,
+            ActiveInput::Replace => &mut self.replace,
+        }
     }
 
     /// Rebuilds matches for the current query and content.
@@ -119,7 +241,7 @@ impl FindPanelState {
 
         let prev_anchor = self.current_match().map(|m| m.start).or(self.last_anchor);
 
-        self.matches = find_all(content, &self.query);
+        self.matches = find_all(content, self.query.text());
 
         if self.matches.is_empty() {
             self.selected_index = 0;
@@ -0,0 +1,365 @@
+//! Code folding for markdown documents.
+//!
+//! A fold hides the lines below a header line: a heading hides its section up to
+//! the next heading of the same or a higher level, a list item hides its nested
+//! items and continuation lines, and a code fence hides the block up to its
+//! closing fence. Folds are remembered by the text of their header line (and
+//! which occurrence of that text it is), so edits elsewhere in the document do
+//! not move them, and they are persisted per file in the state directory.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use crate::markdown::MarkdownHighlighter;
+
+/// Width of the gutter holding the fold chevrons, in pixels.
+pub const FOLD_GUTTER_WIDTH: f32 = 16.0;
+
+/// Columns a tab counts for when comparing list indentation.
+const TAB_WIDTH: usize = 4;
+
+/// What kind of block a fold region covers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FoldKind {
+    Section,
+    List,
+    CodeBlock,
+}
+
+/// A foldable block of lines.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FoldRegion {
+    pub kind: FoldKind,
+    /// Header line, which stays visible when folded
+    pub start: usize,
+    /// Last hidden line, inclusive
+    pub end: usize,
+}
+
+impl FoldRegion {
+    /// Returns true when folding this region hides `line`.
+    pub fn hides(&self, line: usize) -> bool {
+        line > self.start && line <= self.end
+    }
+}
+
+/// Lists every foldable region of `content`, ordered by header line.
+pub fn fold_regions(content: &str) -> Vec<FoldRegion> {
+    let lines: Vec<&str> = content.split('\n').collect();
+    let mut regions = Vec::new();
+
+    // Sections end before the next heading of the same or a higher level
+    let headings = MarkdownHighlighter::headings(content);
+    for (idx, heading) in headings.iter().enumerate() {
+        let next = headings[idx + 1..]
+            .iter()
+            .find(|next| next.level <= heading.level)
+            .map(|next| next.line)
+            .unwrap_or(lines.len());
+        let end = trim_blank_tail(&lines, heading.line, next - 1);
+        if end > heading.line {
+            regions.push(FoldRegion {
+                kind: FoldKind::Section,
+                start: heading.line,
+                end,
+            });
+        }
+    }
+
+    let mut fence_start = None;
+    for (idx, line) in lines.iter().enumerate() {
+        // Same fence rule as the highlighter
+        if line.starts_with("```") {
+            match fence_start.take() {
+                Some(start) => regions.push(FoldRegion {
+                    kind: FoldKind::CodeBlock,
+                    start,
+                    end: idx,
+                }),
+                None => fence_start = Some(idx),
+            }
+            continue;
+        }
+        if fence_start.is_some() {
+            continue;
+        }
+
+        // A list item owns the following lines that are indented deeper
+        let Some(indent) = list_item_indent(line) else {
+            continue;
+        };
+        let mut end = idx;
+        for (next_idx, next) in lines.iter().enumerate().skip(idx + 1) {
+            if next.trim().is_empty() {
+                continue;
+            }
+            if indent_width(next) <= indent {
+                break;
+            }
+            end = next_idx;
+        }
+        if end > idx {
+            regions.push(FoldRegion {
+                kind: FoldKind::List,
+                start: idx,
+                end,
+            });
+        }
+    }
+
+    regions.sort_by_key(|region| region.start);
+    regions
+}
+
+/// Returns the document lines that stay visible with `folded` regions collapsed.
+pub fn visible_lines(line_count: usize, folded: &[FoldRegion]) -> Vec<usize> {
+    let mut fold_ends: HashMap<usize, usize> = HashMap::new();
+    for region in folded {
+        let end = fold_ends.entry(region.start).or_insert(region.end);
+        *end = (*end).max(region.end);
+    }
+
+    let mut visible = Vec::with_capacity(line_count);
+    let mut line = 0;
+    while line < line_count {
+        visible.push(line);
+        // Jump over the fold starting here; folds nested inside it are skipped with it
+        line = fold_ends.get(&line).copied().unwrap_or(line) + 1;
+    }
+    visible
+}
+
+/// Moves `end` up past blank lines so a folded block keeps its trailing spacing.
+fn trim_blank_tail(lines: &[&str], start: usize, mut end: usize) -> usize {
+    while end > start && lines[end].trim().is_empty() {
+        end -= 1;
+    }
+    end
+}
+
+/// Indentation of a list item line (`- `, `* `, `+ `, `1. ` or `1) `), if it is one.
+fn list_item_indent(line: &str) -> Option<usize> {
+    let rest = line.trim_start_matches([' ', '\t']);
+    let is_bullet = ["- ", "* ", "+ "]
+        .iter()
+        .any(|marker| rest.starts_with(marker))
+        || matches!(rest, "-" | "*" | "+");
+    let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+    let is_ordered = digits > 0
+        && matches!(rest.as_bytes().get(digits), Some(b'.' | b')'))
+        && matches!(rest.as_bytes().get(digits + 1), None | Some(b' '));
+    (is_bullet || is_ordered).then(|| indent_width(line))
+}
+
+/// Width of the leading whitespace, counting tabs as `TAB_WIDTH` columns.
+fn indent_width(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| *c == ' ' || *c == '\t')
+        .map(|c| if c == '\t' { TAB_WIDTH } else { 1 })
+        .sum()
+}
+
+/// Identifies a folded header line independently of its line number.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct FoldKey {
+    /// Full text of the header line
+    text: String,
+    /// How many identical lines precede it
+    occurrence: usize,
+}
+
+/// Which regions of one document are folded, persisted per file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FoldState {
+    /// State file name; `None` for buffers without a file
+    state_name: Option<String>,
+    folded: Vec<FoldKey>,
+}
+
+impl FoldState {
+    /// Loads the folds saved for `path`, or starts empty for unsaved buffers.
+    pub fn load(path: Option<&Path>) -> Self {
+        let Some(path) = path else {
+            return Self::default();
+        };
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let state_name = crate::state::keyed_name("folds", &path);
+        let folded = crate::state::read_lines(&state_name)
+            .iter()
+            .filter_map(|line| {
+                let (occurrence, text) = line.split_once('\t')?;
+                Some(FoldKey {
+                    text: text.to_string(),
+                    occurrence: occurrence.parse().ok()?,
+                })
+            })
+            .collect();
+
+        Self {
+            state_name: Some(state_name),
+            folded,
+        }
+    }
+
+    /// Keeps the folds when the document is saved under a new path.
+    pub fn rename(&mut self, path: &Path) {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        self.state_name = Some(crate::state::keyed_name("folds", &path));
+        self.save();
+    }
+
+    /// Returns true when nothing is folded.
+    pub fn is_empty(&self) -> bool {
+        self.folded.is_empty()
+    }
+
+    /// Returns the regions of `content` that are currently folded.
+    pub fn resolve(&self, content: &str, regions: &[FoldRegion]) -> Vec<FoldRegion> {
+        if self.folded.is_empty() {
+            return Vec::new();
+        }
+        let keys = header_keys(content, regions);
+        regions
+            .iter()
+            .filter(|region| {
+                keys.get(&region.start)
+                    .is_some_and(|key| self.folded.contains(key))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Folds the region with header line `region.start`.
+    pub fn fold(&mut self, content: &str, region: &FoldRegion) {
+        let key = header_key(content, region.start);
+        if !self.folded.contains(&key) {
+            self.folded.push(key);
+            self.save();
+        }
+    }
+
+    /// Unfolds the region with header line `region.start`.
+    pub fn unfold(&mut self, content: &str, region: &FoldRegion) {
+        let key = header_key(content, region.start);
+        let before = self.folded.len();
+        self.folded.retain(|folded| folded != &key);
+        if self.folded.len() != before {
+            self.save();
+        }
+    }
+
+    /// Folds every region of the document.
+    pub fn fold_all(&mut self, content: &str, regions: &[FoldRegion]) {
+        self.folded = header_keys(content, regions).into_values().collect();
+        self.save();
+    }
+
+    /// Unfolds everything.
+    pub fn unfold_all(&mut self) {
+        self.folded.clear();
+        self.save();
+    }
+
+    fn save(&self) {
+        let Some(state_name) = &self.state_name else {
+            return;
+        };
+        let lines: Vec<String> = self
+            .folded
+            .iter()
+            .map(|key| format!("{}\t{}", key.occurrence, key.text))
+            .collect();
+        crate::state::write_lines(state_name, &lines);
+    }
+}
+
+/// Keys for the header lines of `regions`, by line number.
+fn header_keys(content: &str, regions: &[FoldRegion]) -> HashMap<usize, FoldKey> {
+    let starts: HashSet<usize> = regions.iter().map(|region| region.start).collect();
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    let mut keys = HashMap::new();
+    for (idx, line) in content.split('\n').enumerate() {
+        let occurrence = seen.entry(line).or_insert(0);
+        if starts.contains(&idx) {
+            keys.insert(
+                idx,
+                FoldKey {
+                    text: line.to_string(),
+                    occurrence: *occurrence,
+                },
+            );
+        }
+        *occurrence += 1;
+    }
+    keys
+}
+
+/// Key for a single header line.
+fn header_key(content: &str, line: usize) -> FoldKey {
+    let lines: Vec<&str> = content.split('\n').collect();
+    let text = lines.get(line).copied().unwrap_or_default();
+    FoldKey {
+        text: text.to_string(),
+        occurrence: lines[..line.min(lines.len())]
+            .iter()
+            .filter(|other| **other == text)
+            .count(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn region(kind: FoldKind, start: usize, end: usize) -> FoldRegion {
+        FoldRegion { kind, start, end }
+    }
+
+    #[test]
+    fn section_ends_before_next_heading_of_same_or_higher_level() {
+        let content = "# A\ntext\n## B\nmore\n# C\nlast";
+        assert_eq!(
+            fold_regions(content),
+            vec![
+                region(FoldKind::Section, 0, 3),
+                region(FoldKind::Section, 2, 3),
+                region(FoldKind::Section, 4, 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn section_keeps_trailing_blank_lines_visible() {
+        let content = "# A\ntext\n\n\n# B";
+        assert_eq!(fold_regions(content), vec![region(FoldKind::Section, 0, 1)]);
+    }
+
+    #[test]
+    fn heading_without_body_is_not_foldable() {
+        assert!(fold_regions("# A\n# B").is_empty());
+    }
+
+    #[test]
+    fn list_item_ends_at_its_last_deeper_line() {
+        let content = "- a\n  - b\n\n  more\n- c";
+        assert_eq!(fold_regions(content), vec![region(FoldKind::List, 0, 3)]);
+    }
+
+    #[test]
+    fn code_block_ends_at_closing_fence() {
+        let content = "```\n- not a list\n  nested\n```\nafter";
+        assert_eq!(
+            fold_regions(content),
+            vec![region(FoldKind::CodeBlock, 0, 3)]
+        );
+    }
+
+    #[test]
+    fn folded_lines_are_skipped_with_nested_folds() {
+        let folded = [
+            region(FoldKind::Section, 0, 3),
+            region(FoldKind::Section, 1, 2),
+        ];
+        assert_eq!(visible_lines(5, &folded), vec![0, 4]);
+    }
+}
@@ -0,0 +1,169 @@
+//! Per-workspace history of opened files, ranked by frecency.
+//!
+//! Frecency combines how often and how recently a file was opened, so files
+//! used every day outrank a file opened many times last year. History is kept
+//! in the state directory under a file name derived from the workspace root.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::file_ops::relocate;
+
+/// Maximum number of files remembered per workspace.
+const HISTORY_LIMIT: usize = 200;
+
+/// Recency buckets as (maximum age in seconds, weight).
+const RECENCY_WEIGHTS: [(u64, u32); 5] = [
+    (4 * 60 * 60, 100),
+    (24 * 60 * 60, 80),
+    (7 * 24 * 60 * 60, 60),
+    (30 * 24 * 60 * 60, 40),
+    (90 * 24 * 60 * 60, 20),
+];
+
+/// Weight for anything older than the last bucket.
+const STALE_WEIGHT: u32 = 10;
+
+/// How a file has been used, for ranking.
+#[derive(Clone, Copy, Debug)]
+pub struct FileUsage {
+    /// Seconds since the Unix epoch
+    pub last_opened: u64,
+    /// Open count weighted by recency
+    pub frecency: u32,
+}
+
+/// One remembered file.
+#[derive(Clone, Debug)]
+struct OpenRecord {
+    path: PathBuf,
+    count: u32,
+    /// Seconds since the Unix epoch
+    last_opened: u64,
+}
+
+/// Open counts and timestamps for the files of one workspace.
+#[derive(Clone, Debug)]
+pub struct OpenHistory {
+    /// State file name for this workspace
+    state_name: String,
+    records: Vec<OpenRecord>,
+}
+
+impl OpenHistory {
+    /// Loads the history for the workspace rooted at `root`.
+    pub fn load(root: &Path) -> Self {
+        let state_name = crate::state::keyed_name("open_history", root);
+        let records = crate::state::read_lines(&state_name)
+            .iter()
+            .filter_map(|line| {
+                let mut parts = line.splitn(3, '\t');
+                let count = parts.next()?.parse().ok()?;
+                let last_opened = parts.next()?.parse().ok()?;
+                let path = PathBuf::from(parts.next()?);
+                Some(OpenRecord {
+                    path,
+                    count,
+                    last_opened,
+                })
+            })
+            .collect();
+
+        Self {
+            state_name,
+            records,
+        }
+    }
+
+    /// Records that `path` was opened now and persists the history.
+    pub fn record_open(&mut self, path: &Path) {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let now = now_secs();
+
+        match self.records.iter_mut().find(|record| record.path == path) {
+            Some(record) => {
+                record.count = record.count.saturating_add(1);
+                record.last_opened = now;
+            }
+            None => self.records.push(OpenRecord {
+                path,
+                count: 1,
+                last_opened: now,
+            }),
+        }
+
+        if self.records.len() > HISTORY_LIMIT {
+            // Forget the least valuable entries first
+            self.records
+                .sort_by_key(|record| std::cmp::Reverse(Self::record_score(record, now)));
+            self.records.truncate(HISTORY_LIMIT);
+        }
+
+        self.save();
+    }
+
+    /// Carries the history of a moved or renamed file, or of every file in a
+    /// moved directory, over to the new path.
+    pub fn rename(&mut self, from: &Path, to: &Path) {
+        let to = to.canonicalize().unwrap_or_else(|_| to.to_path_buf());
+        let mut changed = false;
+        for record in &mut self.records {
+            if let Some(path) = relocate(&record.path, from, &to) {
+                record.path = path;
+                changed = true;
+            }
+        }
+        if changed {
+            self.save();
+        }
+    }
+
+    fn save(&self) {
+        let lines: Vec<String> = self
+            .records
+            .iter()
+            .map(|record| {
+                format!(
+                    "{}\t{}\t{}",
+                    record.count,
+                    record.last_opened,
+                    record.path.to_string_lossy()
+                )
+            })
+            .collect();
+        crate::state::write_lines(&self.state_name, &lines);
+    }
+
+    /// Usage of every remembered file, keyed by path, for fast lookups while ranking.
+    pub fn usage(&self) -> HashMap<PathBuf, FileUsage> {
+        let now = now_secs();
+        self.records
+            .iter()
+            .map(|record| {
+                let usage = FileUsage {
+                    last_opened: record.last_opened,
+                    frecency: Self::record_score(record, now),
+                };
+                (record.path.clone(), usage)
+            })
+            .collect()
+    }
+
+    fn record_score(record: &OpenRecord, now: u64) -> u32 {
+        let age = now.saturating_sub(record.last_opened);
+        let weight = RECENCY_WEIGHTS
+            .iter()
+            .find(|(max_age, _)| age <= *max_age)
+            .map(|(_, weight)| *weight)
+            .unwrap_or(STALE_WEIGHT);
+        record.count.saturating_mul(weight)
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
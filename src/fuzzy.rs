@@ -0,0 +1,434 @@
+//! Fuzzy matching used by the palette.
+//!
+//! Scoring follows the optimal-alignment approach popularized by fzf's v2
+//! algorithm: a Smith-Waterman style dynamic program finds the placement of
+//! the query characters that maximizes the score, instead of greedily taking
+//! the first occurrence of each character. Matches earn bonuses at word
+//! boundaries, camelCase humps and inside the file name of a path, while gaps
+//! between matched characters are penalized.
+//!
+//! A query is split on whitespace into tokens that must all match, in any
+//! order. Matching is case-insensitive unless the query contains an uppercase
+//! character (smart case).
+
+use gpui::BackgroundExecutor;
+
+/// Score for every matched character.
+const SCORE_MATCH: i32 = 16;
+/// Penalty for the first skipped character between two matches.
+const SCORE_GAP_START: i32 = -3;
+/// Penalty for every further skipped character.
+const SCORE_GAP_EXTENSION: i32 = -1;
+
+/// Match right after whitespace or at the start of the text.
+const BONUS_BOUNDARY_WHITE: i32 = SCORE_MATCH / 2 + 2;
+/// Match right after a path separator.
+const BONUS_BOUNDARY_DELIMITER: i32 = SCORE_MATCH / 2 + 1;
+/// Match right after punctuation such as `-`, `_` or `.`.
+const BONUS_BOUNDARY: i32 = SCORE_MATCH / 2;
+/// Matching a non-word character is as good as matching a boundary.
+const BONUS_NON_WORD: i32 = SCORE_MATCH / 2;
+/// Match on a camelCase hump or the first digit of a number.
+const BONUS_CAMEL: i32 = BONUS_BOUNDARY - 1;
+/// Minimum bonus for a match directly following another match.
+const BONUS_CONSECUTIVE: i32 = -(SCORE_GAP_START + SCORE_GAP_EXTENSION);
+/// Extra bonus for matches inside the file name part of a path.
+const BONUS_FILE_NAME: i32 = 4;
+/// The first query character's bonus counts this many times.
+const BONUS_FIRST_CHAR_MULTIPLIER: i32 = 2;
+
+/// Sentinel for unreachable cells of the score matrix.
+const UNREACHABLE: i32 = i32::MIN / 2;
+
+/// Below this many items, matching stays on the calling thread.
+const PARALLEL_THRESHOLD: usize = 2048;
+
+/// Character classes used to find word boundaries.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+    Whitespace,
+    Delimiter,
+    Punctuation,
+    Lower,
+    Upper,
+    Digit,
+    Other,
+}
+
+impl CharClass {
+    fn of(c: char) -> Self {
+        match c {
+            c if c.is_whitespace() => CharClass::Whitespace,
+            '/' | '\\' => CharClass::Delimiter,
+            c if c.is_lowercase() => CharClass::Lower,
+            c if c.is_uppercase() => CharClass::Upper,
+            c if c.is_numeric() => CharClass::Digit,
+            c if c.is_alphabetic() => CharClass::Other,
+            _ => CharClass::Punctuation,
+        }
+    }
+
+    fn is_word(self) -> bool {
+        !matches!(
+            self,
+            CharClass::Whitespace | CharClass::Delimiter | CharClass::Punctuation
+        )
+    }
+}
+
+/// Bonus for matching a character of class `current` that follows one of class `previous`.
+fn boundary_bonus(previous: CharClass, current: CharClass) -> i32 {
+    if !current.is_word() {
+        return BONUS_NON_WORD;
+    }
+    match previous {
+        CharClass::Whitespace => BONUS_BOUNDARY_WHITE,
+        CharClass::Delimiter => BONUS_BOUNDARY_DELIMITER,
+        CharClass::Punctuation => BONUS_BOUNDARY,
+        CharClass::Lower if current == CharClass::Upper => BONUS_CAMEL,
+        previous if current == CharClass::Digit && previous != CharClass::Digit => BONUS_CAMEL,
+        _ => 0,
+    }
+}
+
+/// Lowercases a single character while keeping a one-to-one char mapping,
+/// so match positions stay valid for the original text.
+fn fold_case(c: char) -> char {
+    if c.is_ascii() {
+        c.to_ascii_lowercase()
+    } else {
+        c.to_lowercase().next().unwrap_or(c)
+    }
+}
+
+/// Reusable buffers for scoring, so matching many targets does not allocate per target.
+#[derive(Default)]
+pub struct Scratch {
+    /// Target characters, case-folded unless matching is case-sensitive
+    chars: Vec<char>,
+    /// Positional bonus of every target character
+    bonus: Vec<i32>,
+    /// Best score with the query char matched exactly at this position
+    matched: Vec<i32>,
+    /// Best score with the query char matched at or before this position
+    best: Vec<i32>,
+    /// Window column where the alignment behind `best` matched
+    best_column: Vec<usize>,
+    /// Bonus carried along a run of consecutive matches
+    run_bonus: Vec<i32>,
+    /// Whether `matched` continues a consecutive run from the previous diagonal
+    consecutive: Vec<bool>,
+}
+
+/// A parsed query, ready to score many targets.
+pub struct Matcher {
+    /// Whitespace-separated query tokens, case-folded unless `case_sensitive`
+    tokens: Vec<Vec<char>>,
+    case_sensitive: bool,
+    /// Whether targets are paths whose file name part earns extra bonus
+    file_name_bonus: bool,
+}
+
+impl Matcher {
+    /// Parses `query` into tokens. Uppercase anywhere makes matching case-sensitive.
+    pub fn new(query: &str) -> Self {
+        let case_sensitive = query.chars().any(char::is_uppercase);
+        let tokens = query
+            .split_whitespace()
+            .map(|token| {
+                token
+                    .chars()
+                    .map(|c| if case_sensitive { c } else { fold_case(c) })
+                    .collect()
+            })
+            .collect();
+        Self {
+            tokens,
+            case_sensitive,
+            file_name_bonus: false,
+        }
+    }
+
+    /// Treats targets as paths, favoring matches in the last path component.
+    pub fn with_file_name_bonus(mut self) -> Self {
+        self.file_name_bonus = true;
+        self
+    }
+
+    /// Scores `target`, returning the score (higher is better) and the sorted
+    /// char indices of `target` that matched, or `None` if a token does not match.
+    pub fn score(&self, target: &str, scratch: &mut Scratch) -> Option<(i32, Vec<usize>)> {
+        if self.tokens.is_empty() {
+            return Some((0, Vec::new()));
+        }
+        // Cheap rejection before touching the buffers; most targets fail here
+        if !self
+            .tokens
+            .iter()
+            .all(|token| self.is_subsequence(token, target))
+        {
+            return None;
+        }
+
+        self.prepare(target, scratch);
+        let mut total = 0;
+        let mut indices = Vec::new();
+        for token in &self.tokens {
+            total += Self::align(token, scratch, &mut indices)?;
+        }
+        indices.sort_unstable();
+        indices.dedup();
+        Some((total, indices))
+    }
+
+    /// Scores every item, spreading the work over the background executor's
+    /// threads for large lists and blocking until all of them are scored.
+    ///
+    /// Returns `(item index, score, matched char indices)` for each match, in item order.
+    pub fn match_all<T: Sync>(
+        &self,
+        executor: &BackgroundExecutor,
+        items: &[T],
+        text: impl for<'a> Fn(&'a T) -> &'a str + Sync,
+    ) -> Vec<(usize, i32, Vec<usize>)> {
+        let match_chunk = |offset: usize, chunk: &[T]| {
+            let mut scratch = Scratch::default();
+            chunk
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, item)| {
+                    let (score, indices) = self.score(text(item), &mut scratch)?;
+                    Some((offset + idx, score, indices))
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let workers = executor.num_cpus();
+        if items.len() < PARALLEL_THRESHOLD || workers == 1 {
+            return match_chunk(0, items);
+        }
+
+        // Each chunk writes its matches into its own slot, keeping item order
+        let chunk_size = items.len().div_ceil(workers);
+        let mut slots: Vec<Vec<_>> = items.chunks(chunk_size).map(|_| Vec::new()).collect();
+        executor.block(executor.scoped(|scope| {
+            for ((n, chunk), slot) in items.chunks(chunk_size).enumerate().zip(&mut slots) {
+                let match_chunk = &match_chunk;
+                scope.spawn(async move {
+                    *slot = match_chunk(n * chunk_size, chunk);
+                });
+            }
+        }));
+        slots.into_iter().flatten().collect()
+    }
+
+    fn fold(&self, c: char) -> char {
+        if self.case_sensitive { c } else { fold_case(c) }
+    }
+
+    fn is_subsequence(&self, token: &[char], target: &str) -> bool {
+        let mut remaining = token.iter().peekable();
+        for c in target.chars() {
+            if remaining.peek() == Some(&&self.fold(c)) {
+                remaining.next();
+            }
+        }
+        remaining.peek().is_none()
+    }
+
+    /// Fills the scratch buffers with the folded characters and bonuses of `target`.
+    fn prepare(&self, target: &str, scratch: &mut Scratch) {
+        scratch.chars.clear();
+        scratch.bonus.clear();
+
+        let file_name_start = if self.file_name_bonus {
+            target
+                .rfind(['/', '\\'])
+                .map(|idx| target[..idx].chars().count() + 1)
+                .unwrap_or(0)
+        } else {
+            usize::MAX
+        };
+
+        let mut previous = CharClass::Whitespace;
+        for (idx, c) in target.chars().enumerate() {
+            let class = CharClass::of(c);
+            let mut bonus = boundary_bonus(previous, class);
+            if idx >= file_name_start {
+                bonus += BONUS_FILE_NAME;
+            }
+            scratch.chars.push(self.fold(c));
+            scratch.bonus.push(bonus);
+            previous = class;
+        }
+    }
+
+    /// Finds the best alignment of `token` in the prepared target, appending
+    /// the matched positions to `indices` and returning its score.
+    fn align(token: &[char], scratch: &mut Scratch, indices: &mut Vec<usize>) -> Option<i32> {
+        let (first, last) = (*token.first()?, *token.last()?);
+        // Narrow the matrix to the window between the first possible start and last possible end
+        let start = scratch.chars.iter().position(|&c| c == first)?;
+        let end = scratch.chars.iter().rposition(|&c| c == last)?;
+        if end < start {
+            return None;
+        }
+
+        let width = end - start + 1;
+        let cells = token.len() * width;
+        for buffer in [
+            &mut scratch.matched,
+            &mut scratch.best,
+            &mut scratch.run_bonus,
+        ] {
+            buffer.clear();
+            buffer.resize(cells, UNREACHABLE);
+        }
+        scratch.best_column.clear();
+        scratch.best_column.resize(cells, 0);
+        scratch.consecutive.clear();
+        scratch.consecutive.resize(cells, false);
+
+        for (row, &query_char) in token.iter().enumerate() {
+            let mut in_gap = false;
+            for column in 0..width {
+                let position = start + column;
+                let cell = row * width + column;
+                let bonus = scratch.bonus[position];
+
+                if scratch.chars[position] == query_char {
+                    if row == 0 {
+                        scratch.matched[cell] = SCORE_MATCH + bonus * BONUS_FIRST_CHAR_MULTIPLIER;
+                        scratch.run_bonus[cell] = bonus;
+                    } else if column > 0 {
+                        let diagonal = cell - width - 1;
+
+                        let mut consecutive = UNREACHABLE;
+                        let mut carried = bonus;
+                        if scratch.matched[diagonal] > UNREACHABLE {
+                            carried = bonus
+                                .max(scratch.run_bonus[diagonal])
+                                .max(BONUS_CONSECUTIVE);
+                            consecutive = scratch.matched[diagonal] + SCORE_MATCH + carried;
+                        }
+                        let gapped = match scratch.best[diagonal] {
+                            UNREACHABLE => UNREACHABLE,
+                            score => score + SCORE_MATCH + bonus,
+                        };
+
+                        if consecutive > UNREACHABLE && consecutive >= gapped {
+                            scratch.matched[cell] = consecutive;
+                            scratch.run_bonus[cell] = carried;
+                            scratch.consecutive[cell] = true;
+                        } else if gapped > UNREACHABLE {
+                            scratch.matched[cell] = gapped;
+                            scratch.run_bonus[cell] = bonus;
+                        }
+                    }
+                }
+
+                // Carry the best score so far along the row, paying for skipped chars
+                let skipped = if column > 0 && scratch.best[cell - 1] > UNREACHABLE {
+                    let penalty = if in_gap {
+                        SCORE_GAP_EXTENSION
+                    } else {
+                        SCORE_GAP_START
+                    };
+                    scratch.best[cell - 1] + penalty
+                } else {
+                    UNREACHABLE
+                };
+                if scratch.matched[cell] > UNREACHABLE && scratch.matched[cell] >= skipped {
+                    scratch.best[cell] = scratch.matched[cell];
+                    scratch.best_column[cell] = column;
+                    in_gap = false;
+                } else if skipped > UNREACHABLE {
+                    scratch.best[cell] = skipped;
+                    scratch.best_column[cell] = scratch.best_column[cell - 1];
+                    in_gap = true;
+                }
+            }
+        }
+
+        // Trailing characters after the last match are free
+        let last_row = (token.len() - 1) * width;
+        let (mut column, score) = (0..width)
+            .map(|column| (column, scratch.matched[last_row + column]))
+            .filter(|&(_, score)| score > UNREACHABLE)
+            .fold(None, |best: Option<(usize, i32)>, candidate| match best {
+                Some(best) if best.1 >= candidate.1 => Some(best),
+                _ => Some(candidate),
+            })?;
+
+        for row in (0..token.len()).rev() {
+            indices.push(start + column);
+            if row == 0 {
+                break;
+            }
+            let cell = row * width + column;
+            column = if scratch.consecutive[cell] {
+                column - 1
+            } else {
+                scratch.best_column[cell - width - 1]
+            };
+        }
+
+        Some(score)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(query: &str, target: &str) -> Option<(i32, Vec<usize>)> {
+        Matcher::new(query).score(target, &mut Scratch::default())
+    }
+
+    #[test]
+    fn aligns_to_word_boundary_instead_of_first_occurrence() {
+        let (_, indices) = score("b", "abc_bar").unwrap();
+        assert_eq!(indices, vec![4]);
+    }
+
+    #[test]
+    fn consecutive_matches_beat_scattered_ones() {
+        let (together, _) = score("abc", "abcxx").unwrap();
+        let (scattered, _) = score("abc", "axbxc").unwrap();
+        assert!(together > scattered);
+    }
+
+    #[test]
+    fn longer_gaps_cost_more() {
+        let (short, _) = score("ab", "axb").unwrap();
+        let (long, _) = score("ab", "axxxxb").unwrap();
+        assert!(short > long);
+    }
+
+    #[test]
+    fn file_name_matches_beat_directory_matches() {
+        let matcher = Matcher::new("main").with_file_name_bonus();
+        let mut scratch = Scratch::default();
+        let (in_name, _) = matcher.score("src/main.rs", &mut scratch).unwrap();
+        let (in_dir, _) = matcher.score("main/lib.rs", &mut scratch).unwrap();
+        assert!(in_name > in_dir);
+    }
+
+    #[test]
+    fn tokens_match_in_any_order() {
+        let (_, indices) = score("bar foo", "foo_bar").unwrap();
+        assert_eq!(indices, vec![0, 1, 2, 4, 5, 6]);
+    }
+
+    #[test]
+    fn uppercase_query_is_case_sensitive() {
+        assert!(score("foo", "Foo").is_some());
+        assert!(score("Foo", "foo").is_none());
+    }
+
+    #[test]
+    fn missing_characters_do_not_match() {
+        assert!(score("xyz", "abc").is_none());
+        assert_eq!(score("", "abc"), Some((0, Vec::new())));
+    }
+}
@@ -0,0 +1,351 @@
+//! Ignore rules for workspace file discovery.
+//!
+//! Implements the subset of `.gitignore` semantics that matters for finding
+//! documents: comments, negation with `!`, directory-only patterns with a
+//! trailing `/`, anchored patterns containing `/`, and the `*`, `**`, `?` and
+//! `[...]` wildcards. Rules from deeper ignore files override shallower ones,
+//! and the last matching rule wins, as in git.
+
+use std::path::{Path, PathBuf};
+
+use crate::config::FinderConfig;
+
+/// Ignore files read in every scanned directory, in increasing precedence.
+pub const IGNORE_FILE_NAMES: [&str; 3] = [".gitignore", ".ignore", ".medleyignore"];
+
+/// Directories that are never scanned regardless of configuration.
+const ALWAYS_SKIPPED: [&str; 1] = [".git"];
+
+/// A single parsed ignore pattern.
+#[derive(Clone, Debug)]
+struct Rule {
+    pattern: String,
+    negated: bool,
+    dir_only: bool,
+    /// Anchored patterns match the full relative path; others match any suffix.
+    anchored: bool,
+}
+
+impl Rule {
+    /// Parses one line of an ignore file. Returns `None` for blanks and comments.
+    fn parse(line: &str) -> Option<Self> {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line.strip_prefix('\\').unwrap_or(line)),
+        };
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let anchored = line.contains('/');
+        let pattern = line.trim_start_matches('/');
+        if pattern.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            pattern: pattern.to_string(),
+            negated,
+            dir_only,
+            anchored,
+        })
+    }
+
+    /// Tests the rule against a `/`-separated path relative to the rule's base directory.
+    fn matches(&self, relative: &str, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        if self.anchored {
+            return glob_match(&self.pattern, relative);
+        }
+        let name = relative.rsplit('/').next().unwrap_or(relative);
+        glob_match(&self.pattern, name)
+    }
+}
+
+/// Rules loaded from one ignore source, relative to `base`.
+#[derive(Clone, Debug)]
+struct RuleSet {
+    base: PathBuf,
+    rules: Vec<Rule>,
+}
+
+impl RuleSet {
+    fn parse(base: &Path, content: &str) -> Self {
+        Self {
+            base: base.to_path_buf(),
+            rules: content.lines().filter_map(Rule::parse).collect(),
+        }
+    }
+
+    /// Returns `Some(true)` if ignored, `Some(false)` if re-included, `None` if no rule applies.
+    fn verdict(&self, path: &Path, is_dir: bool) -> Option<bool> {
+        let relative = relative_slash_path(&self.base, path)?;
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.matches(&relative, is_dir))
+            .map(|rule| !rule.negated)
+    }
+}
+
+/// Stack of ignore rules maintained while walking a directory tree.
+///
+/// Call [`IgnoreStack::enter_dir`] before listing a directory and
+/// [`IgnoreStack::leave_dir`] with the returned count afterwards.
+pub struct IgnoreStack {
+    /// Ignore files from the root down to the current directory.
+    layers: Vec<RuleSet>,
+    /// Configured exclude patterns, applied after ignore files.
+    excludes: RuleSet,
+    /// Configured include patterns; empty means everything is included.
+    includes: RuleSet,
+    show_hidden: bool,
+    respect_ignore_files: bool,
+}
+
+impl IgnoreStack {
+    /// Creates a stack for walking `root` with the finder configuration applied.
+    pub fn new(root: &Path, config: &FinderConfig) -> Self {
+        Self {
+            layers: Vec::new(),
+            excludes: RuleSet::parse(root, &config.exclude.join("\n")),
+            includes: RuleSet::parse(root, &config.include.join("\n")),
+            show_hidden: config.show_hidden,
+            respect_ignore_files: config.respect_ignore_files,
+        }
+    }
+
+    /// Loads ignore files found in `dir`. Returns how many layers were pushed.
+    pub fn enter_dir(&mut self, dir: &Path) -> usize {
+        if !self.respect_ignore_files {
+            return 0;
+        }
+
+        let mut pushed = 0;
+        for name in IGNORE_FILE_NAMES {
+            if let Ok(content) = std::fs::read_to_string(dir.join(name)) {
+                let set = RuleSet::parse(dir, &content);
+                if !set.rules.is_empty() {
+                    self.layers.push(set);
+                    pushed += 1;
+                }
+            }
+        }
+        pushed
+    }
+
+    /// Drops the layers pushed by the matching [`IgnoreStack::enter_dir`] call.
+    pub fn leave_dir(&mut self, pushed: usize) {
+        let len = self.layers.len().saturating_sub(pushed);
+        self.layers.truncate(len);
+    }
+
+    /// Returns true when `path` should be skipped (and, for directories, not descended into).
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+        if ALWAYS_SKIPPED.contains(&name.as_ref()) {
+            return true;
+        }
+        if name.starts_with('.') && !self.show_hidden {
+            return true;
+        }
+
+        let mut ignored = false;
+        for layer in &self.layers {
+            if let Some(verdict) = layer.verdict(path, is_dir) {
+                ignored = verdict;
+            }
+        }
+        if let Some(verdict) = self.excludes.verdict(path, is_dir) {
+            ignored = verdict;
+        }
+        ignored
+    }
+
+    /// Returns true when a file passes the configured include patterns.
+    pub fn is_included(&self, path: &Path) -> bool {
+        self.includes.rules.is_empty() || self.includes.verdict(path, false) == Some(true)
+    }
+}
+
+/// Converts `path` to a `/`-separated path relative to `base`.
+fn relative_slash_path(base: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(base).ok()?;
+    let parts: Vec<_> = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect();
+    Some(parts.join("/"))
+}
+
+/// Matches `text` against a gitignore-style glob.
+///
+/// `*` and `?` never match `/`; `**` matches across directories.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    glob_match_chars(&pattern, &text)
+}
+
+fn glob_match_chars(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') if pattern.get(1) == Some(&'*') => {
+            let rest = &pattern[2..];
+            match rest.first() {
+                // Trailing `**` matches everything below.
+                None => true,
+                // `**/` matches zero or more whole directories.
+                Some('/') => {
+                    let rest = &rest[1..];
+                    (0..=text.len())
+                        .filter(|&i| i == 0 || text[i - 1] == '/')
+                        .any(|i| glob_match_chars(rest, &text[i..]))
+                }
+                _ => (0..=text.len()).any(|i| glob_match_chars(rest, &text[i..])),
+            }
+        }
+        Some('*') => {
+            let rest = &pattern[1..];
+            for i in 0..=text.len() {
+                if glob_match_chars(rest, &text[i..]) {
+                    return true;
+                }
+                if i < text.len() && text[i] == '/' {
+                    break;
+                }
+            }
+            false
+        }
+        Some('?') => match text.first() {
+            Some(&c) if c != '/' => glob_match_chars(&pattern[1..], &text[1..]),
+            _ => false,
+        },
+        Some('[') => match (text.first(), parse_class(&pattern[1..])) {
+            (Some(&c), Some((class, consumed))) if c != '/' => {
+                class.matches(c) && glob_match_chars(&pattern[1 + consumed..], &text[1..])
+            }
+            // An unterminated `[` is treated literally.
+            (Some(&'['), None) => glob_match_chars(&pattern[1..], &text[1..]),
+            _ => false,
+        },
+        Some('\\') if pattern.len() > 1 => {
+            text.first() == Some(&pattern[1]) && glob_match_chars(&pattern[2..], &text[1..])
+        }
+        Some(&c) => text.first() == Some(&c) && glob_match_chars(&pattern[1..], &text[1..]),
+    }
+}
+
+/// Character class such as `[a-z]` or `[!0-9]`.
+struct CharClass {
+    negated: bool,
+    ranges: Vec<(char, char)>,
+}
+
+impl CharClass {
+    fn matches(&self, c: char) -> bool {
+        let hit = self.ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi);
+        hit != self.negated
+    }
+}
+
+/// Parses a class body after the opening `[`. Returns the class and chars consumed
+/// including the closing `]`, or `None` when unterminated.
+fn parse_class(body: &[char]) -> Option<(CharClass, usize)> {
+    let mut idx = 0;
+    let negated = matches!(body.first(), Some('!') | Some('^'));
+    if negated {
+        idx += 1;
+    }
+
+    let mut ranges = Vec::new();
+    let mut first = true;
+    while idx < body.len() {
+        let c = body[idx];
+        if c == ']' && !first {
+            return Some((CharClass { negated, ranges }, idx + 1));
+        }
+        first = false;
+        if body.get(idx + 1) == Some(&'-') && body.get(idx + 2).is_some_and(|&hi| hi != ']') {
+            ranges.push((c, body[idx + 2]));
+            idx += 3;
+        } else {
+            ranges.push((c, c));
+            idx += 1;
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn verdict(rules: &str, relative: &str, is_dir: bool) -> Option<bool> {
+        let base = Path::new("/workspace");
+        RuleSet::parse(base, rules).verdict(&base.join(relative), is_dir)
+    }
+
+    #[test]
+    fn negation_re_includes_a_file() {
+        let rules = "*.log\n!keep.log";
+        assert_eq!(verdict(rules, "debug.log", false), Some(true));
+        assert_eq!(verdict(rules, "keep.log", false), Some(false));
+        assert_eq!(verdict(rules, "notes.md", false), None);
+    }
+
+    #[test]
+    fn last_matching_rule_wins() {
+        assert_eq!(verdict("!keep.log\n*.log", "keep.log", false), Some(true));
+    }
+
+    #[test]
+    fn leading_slash_anchors_to_the_base() {
+        assert_eq!(verdict("/build", "build", true), Some(true));
+        assert_eq!(verdict("/build", "src/build", true), None);
+        assert_eq!(verdict("build", "src/build", true), Some(true));
+    }
+
+    #[test]
+    fn inner_slash_anchors_to_the_base() {
+        assert_eq!(verdict("docs/*.md", "docs/a.md", false), Some(true));
+        assert_eq!(verdict("docs/*.md", "src/docs/a.md", false), None);
+    }
+
+    #[test]
+    fn trailing_slash_only_matches_directories() {
+        assert_eq!(verdict("out/", "out", true), Some(true));
+        assert_eq!(verdict("out/", "out", false), None);
+    }
+
+    #[test]
+    fn comments_and_blank_lines_are_skipped() {
+        assert!(
+            RuleSet::parse(Path::new("/"), "# comment\n\n   \n")
+                .rules
+                .is_empty()
+        );
+        assert_eq!(verdict("\\#notes", "#notes", false), Some(true));
+    }
+
+    #[test]
+    fn wildcards() {
+        assert!(glob_match("*.md", "a.md"));
+        assert!(!glob_match("*.md", "docs/a.md"));
+        assert!(glob_match("docs/**/a.md", "docs/x/y/a.md"));
+        assert!(glob_match("docs/**/a.md", "docs/a.md"));
+        assert!(glob_match("file?.txt", "file1.txt"));
+        assert!(glob_match("[a-c]x", "bx"));
+        assert!(!glob_match("[!a-c]x", "bx"));
+    }
+}
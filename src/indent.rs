@@ -0,0 +1,270 @@
+//! Indenting and outdenting lines with Tab and Shift+Tab.
+//!
+//! The indent unit is a tab or a number of spaces, set in the configuration
+//! and, unless turned off, detected from each file as it is opened. Tab indents
+//! every line touched by the selection by one unit and Shift+Tab removes up to
+//! one unit. A list item moves together with the lines nested under it: with
+//! spaces it is nested at the text column of the item above it and un-nested
+//! back to its parent's column, so the result is still a valid markdown list.
+
+use crate::lines::{self, LineEdit};
+use crate::lists::ListItem;
+
+/// One level of indentation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Indent {
+    /// Whether a level is a tab character instead of spaces
+    pub tabs: bool,
+    /// Spaces per level, also how many leading spaces Shift+Tab removes with tabs
+    pub width: usize,
+}
+
+impl Indent {
+    /// Text inserted for one level.
+    pub fn text(self) -> String {
+        if self.tabs {
+            "\t".to_string()
+        } else {
+            " ".repeat(self.width)
+        }
+    }
+
+    /// Indentation used by `content`, or `None` when it has no indented lines.
+    ///
+    /// Tabs win when more lines start with a tab than with spaces; otherwise the
+    /// width is the most common step between consecutive indentation levels.
+    pub fn detect(content: &str) -> Option<Self> {
+        let mut tab_lines = 0;
+        let mut space_lines = 0;
+        let mut steps = [0usize; 9];
+        let mut previous = 0;
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            if line.starts_with('\t') {
+                tab_lines += 1;
+                continue;
+            }
+            let spaces = line.len() - line.trim_start_matches(' ').len();
+            if spaces > 0 {
+                space_lines += 1;
+            }
+            if let Some(count) = spaces
+                .checked_sub(previous)
+                .and_then(|step| steps.get_mut(step))
+            {
+                *count += 1;
+            }
+            previous = spaces;
+        }
+
+        if tab_lines > space_lines {
+            return Some(Self {
+                tabs: true,
+                width: 4,
+            });
+        }
+        let (width, &count) = steps
+            .iter()
+            .enumerate()
+            .skip(2)
+            .max_by_key(|&(step, &count)| (count, std::cmp::Reverse(step)))?;
+        (count > 0).then_some(Self { tabs: false, width })
+    }
+}
+
+/// Indents (or with `outdent`, outdents) the lines touched by the selection.
+///
+/// Returns `None` when no line changes.
+pub fn shift_lines(
+    content: &str,
+    anchor: Option<usize>,
+    head: usize,
+    indent: Indent,
+    outdent: bool,
+) -> Option<LineEdit> {
+    let mut block = lines::touched_lines(content, anchor, head);
+    let last_line = content[..block.end].rfind('\n').map_or(0, |idx| idx + 1);
+    if let Some(item) = ListItem::parse(&content[last_line..block.end]) {
+        block.end = nested_end(content, block.end, item.indent);
+    }
+
+    let first = ListItem::parse(line_at(content, block.start));
+    let spaces = match first.filter(|_| !indent.tabs) {
+        Some(item) if outdent => parent(content, block.start, item.indent)
+            .map_or(indent.width, |parent| item.indent - parent.indent),
+        Some(item) => sibling(content, block.start, item.indent).map_or(indent.width, |sibling| {
+            sibling.text_start - sibling.indent - if sibling.checkbox { 4 } else { 0 }
+        }),
+        None => indent.width,
+    };
+
+    // Per line: start offset in the old document and bytes added or removed
+    let mut shifts: Vec<(usize, isize)> = Vec::new();
+    let mut text = String::new();
+    let mut start = block.start;
+    for line in content[block.clone()].split('\n') {
+        let shift = if outdent {
+            let leading = line.len() - line.trim_start_matches(' ').len();
+            let removed = if line.starts_with('\t') {
+                1
+            } else {
+                leading.min(spaces)
+            };
+            text.push_str(&line[removed..]);
+            -(removed as isize)
+        } else if line.trim().is_empty() {
+            text.push_str(line);
+            0
+        } else {
+            let added = if indent.tabs {
+                "\t".to_string()
+            } else {
+                " ".repeat(spaces)
+            };
+            text.push_str(&added);
+            text.push_str(line);
+            added.len() as isize
+        };
+        text.push('\n');
+        shifts.push((start, shift));
+        start += line.len() + 1;
+    }
+    text.pop();
+    if text == content[block.clone()] {
+        return None;
+    }
+
+    // Offsets move with their line; ones inside removed indentation go to its start
+    let map = |offset: usize| {
+        let mut moved = offset as isize;
+        for &(line_start, shift) in shifts.iter().filter(|(start, _)| *start <= offset) {
+            moved += if shift < 0 {
+                shift.max(line_start as isize - offset as isize)
+            } else {
+                shift
+            };
+        }
+        moved.max(0) as usize
+    };
+    Some(LineEdit {
+        anchor: anchor.map(map),
+        head: map(head),
+        range: block,
+        text,
+    })
+}
+
+/// The line starting at `start`, without its line break.
+fn line_at(content: &str, start: usize) -> &str {
+    let end = content[start..]
+        .find('\n')
+        .map_or(content.len(), |idx| start + idx);
+    &content[start..end]
+}
+
+/// The lines above `start`, nearest first, up to the first blank line.
+fn lines_above(content: &str, start: usize) -> impl Iterator<Item = &str> {
+    content[..start.saturating_sub(1)]
+        .rsplit('\n')
+        .take_while(|line| !line.trim().is_empty())
+}
+
+/// The item above `start` at the same `indent`, before any item it is nested in.
+fn sibling(content: &str, start: usize, indent: usize) -> Option<ListItem> {
+    lines_above(content, start)
+        .filter_map(ListItem::parse)
+        .find(|item| item.indent <= indent)
+        .filter(|item| item.indent == indent)
+}
+
+/// The nearest item above `start` indented less than `indent`.
+fn parent(content: &str, start: usize, indent: usize) -> Option<ListItem> {
+    lines_above(content, start)
+        .filter_map(ListItem::parse)
+        .find(|item| item.indent < indent)
+}
+
+/// End of the lines after `end` that are nested deeper than `indent`.
+fn nested_end(content: &str, end: usize, indent: usize) -> usize {
+    let mut nested_end = end;
+    for line in content[end..].split('\n').skip(1) {
+        let leading = line.len() - line.trim_start_matches([' ', '\t']).len();
+        if line.trim().is_empty() || leading <= indent {
+            break;
+        }
+        nested_end += 1 + line.len();
+    }
+    nested_end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPACES: Indent = Indent {
+        tabs: false,
+        width: 4,
+    };
+
+    /// Applies `shift_lines` with the caret at `head` and returns the new text.
+    fn shifted(content: &str, head: usize, outdent: bool) -> Option<String> {
+        let edit = shift_lines(content, None, head, SPACES, outdent)?;
+        let mut result = content.to_string();
+        result.replace_range(edit.range, &edit.text);
+        Some(result)
+    }
+
+    #[test]
+    fn detects_the_most_common_step() {
+        assert_eq!(
+            Indent::detect("a\n  b\n    c\n  d"),
+            Some(Indent {
+                tabs: false,
+                width: 2
+            })
+        );
+        assert_eq!(
+            Indent::detect("a\n\tb\n\t\tc"),
+            Some(Indent {
+                tabs: true,
+                width: 4
+            })
+        );
+        assert_eq!(Indent::detect("a\nb"), None);
+    }
+
+    #[test]
+    fn indents_and_outdents_plain_lines() {
+        assert_eq!(shifted("text", 0, false).as_deref(), Some("    text"));
+        assert_eq!(shifted("  text", 0, true).as_deref(), Some("text"));
+        assert_eq!(shifted("text", 0, true), None);
+    }
+
+    #[test]
+    fn nests_a_list_item_under_the_text_of_its_sibling() {
+        let content = "1. a\n2. b";
+        assert_eq!(shifted(content, 6, false).as_deref(), Some("1. a\n   2. b"));
+    }
+
+    #[test]
+    fn outdents_a_list_item_to_its_parent_column() {
+        let content = "- a\n  - b";
+        assert_eq!(shifted(content, 6, true).as_deref(), Some("- a\n- b"));
+    }
+
+    #[test]
+    fn nested_lines_move_with_their_item() {
+        let content = "- a\n- b\n  - c\n- d";
+        assert_eq!(
+            shifted(content, 5, false).as_deref(),
+            Some("- a\n  - b\n    - c\n- d")
+        );
+    }
+
+    #[test]
+    fn caret_moves_with_its_line() {
+        let edit = shift_lines("text", None, 2, SPACES, false).unwrap();
+        assert_eq!(edit.head, 6);
+        let edit = shift_lines("    text", None, 2, SPACES, true).unwrap();
+        assert_eq!(edit.head, 0);
+    }
+}
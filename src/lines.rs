@@ -0,0 +1,369 @@
+//! Line manipulation commands.
+//!
+//! Each command works on the whole lines touched by the selection, where a
+//! selection ending at the start of a line does not touch that line, and
+//! produces a single `LineEdit`: one range of the document replaced by new
+//! text, with the selection placed on the lines it was on.
+
+use std::collections::HashSet;
+use std::ops::Range;
+
+/// Replacement of one range of the document, with the selection afterwards.
+#[derive(Debug, PartialEq, Eq)]
+pub struct LineEdit {
+    /// Range of the old document that is replaced
+    pub range: Range<usize>,
+    /// Text put in its place
+    pub text: String,
+    /// Selection anchor in the new document, if anything is selected
+    pub anchor: Option<usize>,
+    /// Caret position in the new document
+    pub head: usize,
+}
+
+/// Lines touched by the selection from `anchor` to `head`, without the final line break.
+pub fn touched_lines(content: &str, anchor: Option<usize>, head: usize) -> Range<usize> {
+    let anchor = anchor.unwrap_or(head);
+    let (start, end) = (anchor.min(head), anchor.max(head));
+    let last = if end > start && content[..end].ends_with('\n') {
+        end - 1
+    } else {
+        end
+    };
+    let first = content[..start].rfind('\n').map_or(0, |idx| idx + 1);
+    let last_end = content[last..]
+        .find('\n')
+        .map_or(content.len(), |idx| last + idx);
+    first..last_end
+}
+
+/// The selection moved by `delta` bytes, as an edit replacing `range` with `text`.
+fn shifted(
+    range: Range<usize>,
+    text: String,
+    anchor: Option<usize>,
+    head: usize,
+    delta: isize,
+) -> LineEdit {
+    LineEdit {
+        range,
+        text,
+        anchor: anchor.map(|anchor| anchor.saturating_add_signed(delta)),
+        head: head.saturating_add_signed(delta),
+    }
+}
+
+/// Swaps the touched lines with the line above (`direction < 0`) or below them.
+///
+/// Returns `None` at the start or end of the document. A final line break
+/// stays at the end: the empty line after it is never swapped.
+pub fn move_lines(
+    content: &str,
+    anchor: Option<usize>,
+    head: usize,
+    direction: isize,
+) -> Option<LineEdit> {
+    let block = touched_lines(content, anchor, head);
+    let lines = &content[block.clone()];
+
+    if direction < 0 {
+        if block.start == 0 || block.start == content.len() {
+            return None;
+        }
+        let above = touched_lines(content, None, block.start - 1);
+        let text = format!("{lines}\n{}", &content[above.clone()]);
+        let delta = -(above.len() as isize + 1);
+        Some(shifted(above.start..block.end, text, anchor, head, delta))
+    } else {
+        if block.end + 1 >= content.len() {
+            return None;
+        }
+        let below = touched_lines(content, None, block.end + 1);
+        let text = format!("{}\n{lines}", &content[below.clone()]);
+        let delta = below.len() as isize + 1;
+        Some(shifted(block.start..below.end, text, anchor, head, delta))
+    }
+}
+
+/// Inserts a copy of the touched lines below them and moves the selection onto the copy.
+pub fn duplicate_lines(content: &str, anchor: Option<usize>, head: usize) -> LineEdit {
+    let block = touched_lines(content, anchor, head);
+    let text = format!("\n{}", &content[block.clone()]);
+    let delta = text.len() as isize;
+    shifted(block.end..block.end, text, anchor, head, delta)
+}
+
+/// Joins the touched lines into one, or the current line with the next.
+///
+/// Leading whitespace of the joined lines is replaced by a single space. A
+/// caret without a selection ends up where the lines were joined; a selection
+/// covers the joined line.
+pub fn join_lines(content: &str, anchor: Option<usize>, head: usize) -> Option<LineEdit> {
+    let mut block = touched_lines(content, anchor, head);
+    let single = !content[block.clone()].contains('\n');
+    if single {
+        if block.end == content.len() {
+            return None;
+        }
+        block.end = touched_lines(content, None, block.end + 1).end;
+    }
+
+    let mut lines = content[block.clone()].split('\n');
+    let mut text = lines.next().unwrap_or_default().to_string();
+    let join_point = block.start + text.len();
+    for line in lines {
+        let line = line.trim_start();
+        if !line.is_empty() && !text.is_empty() && !text.ends_with(char::is_whitespace) {
+            text.push(' ');
+        }
+        text.push_str(line);
+    }
+
+    let edit = if single && anchor.is_none_or(|anchor| anchor == head) {
+        LineEdit {
+            range: block.clone(),
+            anchor: None,
+            head: join_point,
+            text,
+        }
+    } else {
+        LineEdit {
+            range: block.clone(),
+            anchor: Some(block.start),
+            head: block.start + text.len(),
+            text,
+        }
+    };
+    Some(edit)
+}
+
+/// Rewrites the touched lines with `transform`, or every line of the document
+/// when nothing is selected; a final line break stays at the end. Returns
+/// `None` when the lines do not change.
+///
+/// A selection ends up covering the rewritten lines; without one the caret
+/// keeps its offset.
+fn transform_lines(
+    content: &str,
+    anchor: Option<usize>,
+    head: usize,
+    transform: impl FnOnce(&mut Vec<&str>),
+) -> Option<LineEdit> {
+    let selected = anchor.is_some_and(|anchor| anchor != head);
+    let block = if selected {
+        touched_lines(content, anchor, head)
+    } else {
+        0..content.strip_suffix('\n').unwrap_or(content).len()
+    };
+
+    let mut lines: Vec<&str> = content[block.clone()].split('\n').collect();
+    transform(&mut lines);
+    let text = lines.join("\n");
+    if text == content[block.clone()] {
+        return None;
+    }
+
+    let (anchor, head) = if selected {
+        (Some(block.start), block.start + text.len())
+    } else {
+        (None, head.min(block.start + text.len()))
+    };
+    Some(LineEdit {
+        range: block,
+        text,
+        anchor,
+        head,
+    })
+}
+
+/// Sorts the touched lines, ignoring case first.
+pub fn sort_lines(content: &str, anchor: Option<usize>, head: usize) -> Option<LineEdit> {
+    transform_lines(content, anchor, head, |lines| {
+        lines.sort_by(|a, b| a.to_lowercase().cmp(&b.to_lowercase()).then(a.cmp(b)));
+    })
+}
+
+/// Reverses the order of the touched lines.
+pub fn reverse_lines(content: &str, anchor: Option<usize>, head: usize) -> Option<LineEdit> {
+    transform_lines(content, anchor, head, |lines| lines.reverse())
+}
+
+/// Removes repeated lines among the touched lines, keeping the first of each.
+pub fn unique_lines(content: &str, anchor: Option<usize>, head: usize) -> Option<LineEdit> {
+    transform_lines(content, anchor, head, |lines| {
+        let mut seen = HashSet::new();
+        lines.retain(|line| seen.insert(*line));
+    })
+}
+
+/// Swaps the characters on either side of the caret and moves past both; at
+/// the end of a line, swaps the last two characters.
+///
+/// Returns `None` when the line has fewer than two characters around the caret.
+pub fn transpose_chars(content: &str, head: usize) -> Option<LineEdit> {
+    let line = touched_lines(content, None, head);
+    let text = &content[line.clone()];
+    let mut at = head - line.start;
+    if at == text.len() {
+        at = text[..at].char_indices().next_back()?.0;
+    }
+    let before = text[..at].chars().next_back()?;
+    let after = text[at..].chars().next()?;
+
+    let start = line.start + at - before.len_utf8();
+    let end = line.start + at + after.len_utf8();
+    Some(LineEdit {
+        range: start..end,
+        text: format!("{after}{before}"),
+        anchor: None,
+        head: end,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Applies `edit` to `content` and returns the new text.
+    fn applied(content: &str, edit: Option<LineEdit>) -> Option<String> {
+        let edit = edit?;
+        let mut result = content.to_string();
+        result.replace_range(edit.range, &edit.text);
+        Some(result)
+    }
+
+    #[test]
+    fn touched_lines_skip_a_line_the_selection_only_reaches() {
+        let content = "one\ntwo\nthree";
+        assert_eq!(touched_lines(content, None, 5), 4..7);
+        assert_eq!(touched_lines(content, Some(1), 5), 0..7);
+        assert_eq!(touched_lines(content, Some(0), 4), 0..3);
+        assert_eq!(touched_lines(content, Some(8), 4), 4..7);
+        assert_eq!(touched_lines(content, Some(9), 4), 4..13);
+    }
+
+    #[test]
+    fn moves_lines_and_the_selection_with_them() {
+        let content = "one\ntwo\nthree";
+        let edit = move_lines(content, Some(4), 6, -1).unwrap();
+        assert_eq!((edit.anchor, edit.head), (Some(0), 2));
+        assert_eq!(
+            applied(content, Some(edit)).as_deref(),
+            Some("two\none\nthree")
+        );
+
+        let edit = move_lines(content, None, 1, 1).unwrap();
+        assert_eq!(edit.head, 5);
+        assert_eq!(
+            applied(content, Some(edit)).as_deref(),
+            Some("two\none\nthree")
+        );
+
+        assert_eq!(move_lines(content, None, 1, -1), None);
+        assert_eq!(move_lines(content, None, 10, 1), None);
+    }
+
+    #[test]
+    fn moving_lines_keeps_a_final_line_break_at_the_end() {
+        let content = "one\ntwo\n";
+        assert_eq!(move_lines(content, None, 5, 1), None);
+        assert_eq!(move_lines(content, None, 8, -1), None);
+        assert_eq!(
+            applied(content, move_lines(content, None, 1, 1)).as_deref(),
+            Some("two\none\n")
+        );
+        assert_eq!(
+            applied(content, move_lines(content, Some(0), 8, -1)),
+            None,
+            "a selection of every line has nothing to move past"
+        );
+    }
+
+    #[test]
+    fn duplicates_below_and_selects_the_copy() {
+        let content = "one\ntwo";
+        let edit = duplicate_lines(content, None, 1);
+        assert_eq!(edit.head, 5);
+        assert_eq!(
+            applied(content, Some(edit)).as_deref(),
+            Some("one\none\ntwo")
+        );
+    }
+
+    #[test]
+    fn joins_lines_with_a_single_space() {
+        let content = "one\n   two\nthree";
+        let edit = join_lines(content, None, 1).unwrap();
+        assert_eq!((edit.anchor, edit.head), (None, 3));
+        assert_eq!(
+            applied(content, Some(edit)).as_deref(),
+            Some("one two\nthree")
+        );
+
+        let edit = join_lines(content, Some(0), 13).unwrap();
+        assert_eq!((edit.anchor, edit.head), (Some(0), 13));
+        assert_eq!(
+            applied(content, Some(edit)).as_deref(),
+            Some("one two three")
+        );
+
+        assert_eq!(join_lines(content, None, 14), None);
+    }
+
+    #[test]
+    fn sorts_reverses_and_dedupes_the_selected_lines() {
+        let content = "b\nC\na\nb\nz";
+        let edit = sort_lines(content, Some(0), 7).unwrap();
+        assert_eq!((edit.anchor, edit.head), (Some(0), 7));
+        assert_eq!(
+            applied(content, Some(edit)).as_deref(),
+            Some("a\nb\nb\nC\nz")
+        );
+
+        assert_eq!(
+            applied(content, reverse_lines(content, Some(2), 7)).as_deref(),
+            Some("b\nb\na\nC\nz")
+        );
+        assert_eq!(
+            applied(content, unique_lines(content, Some(0), 7)).as_deref(),
+            Some("b\nC\na\nz")
+        );
+    }
+
+    #[test]
+    fn sorts_reverses_and_dedupes_the_whole_document_without_a_selection() {
+        let content = "b\na\nb\n";
+        let edit = sort_lines(content, None, 2).unwrap();
+        assert_eq!((edit.anchor, edit.head), (None, 2));
+        assert_eq!(applied(content, Some(edit)).as_deref(), Some("a\nb\nb\n"));
+
+        assert_eq!(
+            applied("a\nb\n", reverse_lines("a\nb\n", Some(2), 2)).as_deref(),
+            Some("b\na\n")
+        );
+        let edit = unique_lines(content, None, 6).unwrap();
+        assert_eq!(edit.head, 3);
+        assert_eq!(applied(content, Some(edit)).as_deref(), Some("b\na\n"));
+
+        assert_eq!(sort_lines("a\nb\n", None, 0), None);
+    }
+
+    #[test]
+    fn transposes_around_the_caret_or_at_the_line_end() {
+        let content = "abc\néx";
+        let edit = transpose_chars(content, 1).unwrap();
+        assert_eq!(edit.head, 2);
+        assert_eq!(applied(content, Some(edit)).as_deref(), Some("bac\néx"));
+
+        assert_eq!(
+            applied(content, transpose_chars(content, 3)).as_deref(),
+            Some("acb\néx")
+        );
+        assert_eq!(
+            applied(content, transpose_chars(content, 7)).as_deref(),
+            Some("abc\nxé")
+        );
+        assert_eq!(transpose_chars(content, 0), None);
+        assert_eq!(transpose_chars("a", 1), None);
+    }
+}
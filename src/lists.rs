@@ -0,0 +1,282 @@
+//! Markdown list items and blockquotes, for continuing them on Enter and
+//! renumbering them after nesting.
+//!
+//! Enter inside a list item starts the next item with the same indentation and
+//! marker: bullets repeat, ordered numbers count up and checkboxes restart
+//! unchecked. Inside a blockquote the `>` prefix repeats, and on an indented
+//! line the indentation does. Enter on an item or quote line with no text ends
+//! the list or quote instead.
+
+use std::ops::Range;
+
+use crate::markdown::MarkdownHighlighter;
+
+/// The marker of a list item.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Marker {
+    /// `-`, `*` or `+`
+    Bullet(char),
+    /// A number followed by `.` or `)`
+    Ordered { number: u64, delimiter: char },
+}
+
+/// A line that starts a list item.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ListItem {
+    /// Byte length of the indentation before the marker
+    pub indent: usize,
+    pub marker: Marker,
+    /// Whether the marker is followed by a `[ ]` or `[x]` checkbox
+    pub checkbox: bool,
+    /// Byte offset where the item's text starts
+    pub text_start: usize,
+}
+
+impl ListItem {
+    /// Parses the list marker at the start of `line`, if there is one.
+    pub fn parse(line: &str) -> Option<Self> {
+        let text_start = MarkdownHighlighter::text_start(line);
+        let indent = line.len() - line.trim_start_matches([' ', '\t']).len();
+        let rest = &line[indent..text_start];
+
+        let first = rest.chars().next()?;
+        let marker = match first {
+            '-' | '*' | '+' => Marker::Bullet(first),
+            '0'..='9' => {
+                let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+                Marker::Ordered {
+                    number: rest[..digits].parse().ok()?,
+                    delimiter: rest[digits..].chars().next()?,
+                }
+            }
+            _ => return None,
+        };
+        Some(Self {
+            indent,
+            marker,
+            checkbox: rest.ends_with("] "),
+            text_start,
+        })
+    }
+
+    /// Marker text (with checkbox) for the item `step` items after this one,
+    /// or `None` when its number does not fit in a `u64`.
+    pub fn marker_text(&self, step: u64) -> Option<String> {
+        let marker = match self.marker {
+            Marker::Bullet(bullet) => format!("{bullet} "),
+            Marker::Ordered { number, delimiter } => {
+                format!("{}{delimiter} ", number.checked_add(step)?)
+            }
+        };
+        Some(if self.checkbox {
+            format!("{marker}[ ] ")
+        } else {
+            marker
+        })
+    }
+}
+
+/// What a line break at the end of a list item or quoted line does.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Continuation {
+    /// Start the new line with this prefix
+    Next(String),
+    /// The line has no text: replace it with this prefix to end the list or quote
+    End(String),
+}
+
+/// Continuation of `line` after a line break, or `None` for a plain line break.
+pub fn continuation(line: &str) -> Option<Continuation> {
+    let indent = &line[..line.len() - line.trim_start_matches([' ', '\t']).len()];
+    let rest = &line[indent.len()..];
+
+    if rest.starts_with('>') {
+        let text_start = MarkdownHighlighter::text_start(line);
+        let prefix = format!("{indent}> ");
+        let inner = &line[text_start..];
+        return Some(match continuation(inner) {
+            Some(Continuation::Next(next)) => Continuation::Next(prefix + &next),
+            Some(Continuation::End(_)) => Continuation::End(prefix),
+            None if inner.trim().is_empty() => Continuation::End(String::new()),
+            None => Continuation::Next(prefix),
+        });
+    }
+
+    if let Some(item) = ListItem::parse(line) {
+        if line[item.text_start..].trim().is_empty() {
+            return Some(Continuation::End(String::new()));
+        }
+        // A number that cannot count up any further ends the list
+        if let Some(marker) = item.marker_text(1) {
+            return Some(Continuation::Next(format!("{indent}{marker}")));
+        }
+    }
+
+    (!indent.is_empty()).then(|| Continuation::Next(indent.to_string()))
+}
+
+/// Number changes that make the ordered lists around `offset` count up again.
+///
+/// Covers the run of non-blank lines containing `offset`. Each ordered list
+/// counts from its first item, except that a nested list starts at 1, so an
+/// item nested under another starts a new list and the items left behind
+/// close the gap. Returns the ranges of the numbers to replace, in document order.
+pub fn renumber(content: &str, offset: usize) -> Vec<(Range<usize>, String)> {
+    let mut start = content[..offset].rfind('\n').map_or(0, |idx| idx + 1);
+    while start > 0 {
+        let previous = content[..start - 1].rfind('\n').map_or(0, |idx| idx + 1);
+        if content[previous..start - 1].trim().is_empty() {
+            break;
+        }
+        start = previous;
+    }
+
+    // Open lists, outermost first: indentation, marker and the next number
+    let mut open: Vec<(usize, Marker, u64)> = Vec::new();
+    let mut changes = Vec::new();
+    let mut line_start = start;
+    for line in content[start..].split('\n') {
+        if line.trim().is_empty() {
+            break;
+        }
+        let item = ListItem::parse(line);
+        let indent = item.map_or_else(
+            || line.len() - line.trim_start_matches([' ', '\t']).len(),
+            |item| item.indent,
+        );
+        // A line closes the lists nested deeper than it
+        while open
+            .last()
+            .is_some_and(|&(open_indent, _, _)| open_indent > indent)
+        {
+            open.pop();
+        }
+
+        if let Some(item) = item {
+            let continues = open.last().is_some_and(|&(open_indent, marker, _)| {
+                open_indent == item.indent && same_kind(marker, item.marker)
+            });
+            if !continues {
+                if open
+                    .last()
+                    .is_some_and(|&(open_indent, _, _)| open_indent == item.indent)
+                {
+                    open.pop();
+                }
+                let first = match item.marker {
+                    Marker::Ordered { number, .. } if open.is_empty() => number,
+                    _ => 1,
+                };
+                open.push((item.indent, item.marker, first));
+            }
+
+            if let Some((_, _, next)) = open.last_mut() {
+                match item.marker {
+                    Marker::Ordered { number, .. } if number != *next => {
+                        let digits = line[item.indent..]
+                            .bytes()
+                            .take_while(u8::is_ascii_digit)
+                            .count();
+                        let at = line_start + item.indent;
+                        changes.push((at..at + digits, next.to_string()));
+                    }
+                    _ => {}
+                }
+                *next = next.saturating_add(1);
+            }
+        }
+        line_start += line.len() + 1;
+    }
+    changes
+}
+
+/// Whether two markers belong to the same list: the same bullet, or numbers
+/// with the same delimiter.
+fn same_kind(a: Marker, b: Marker) -> bool {
+    match (a, b) {
+        (Marker::Bullet(a), Marker::Bullet(b)) => a == b,
+        (Marker::Ordered { delimiter: a, .. }, Marker::Ordered { delimiter: b, .. }) => a == b,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Applies `renumber` at `offset` and returns the resulting text.
+    fn renumbered(content: &str, offset: usize) -> String {
+        let mut result = content.to_string();
+        for (range, number) in renumber(content, offset).into_iter().rev() {
+            result.replace_range(range, &number);
+        }
+        result
+    }
+
+    #[test]
+    fn parses_markers() {
+        let item = ListItem::parse("  - [x] done").unwrap();
+        assert_eq!(item.indent, 2);
+        assert_eq!(item.marker, Marker::Bullet('-'));
+        assert!(item.checkbox);
+        assert_eq!(item.text_start, 8);
+
+        let item = ListItem::parse("12) twelve").unwrap();
+        assert_eq!(
+            item.marker,
+            Marker::Ordered {
+                number: 12,
+                delimiter: ')'
+            }
+        );
+        assert_eq!(item.text_start, 4);
+        assert!(ListItem::parse("plain").is_none());
+    }
+
+    #[test]
+    fn continues_items_and_quotes() {
+        assert_eq!(
+            continuation("1. one"),
+            Some(Continuation::Next("2. ".to_string()))
+        );
+        assert_eq!(
+            continuation("- [x] done"),
+            Some(Continuation::Next("- [ ] ".to_string()))
+        );
+        assert_eq!(
+            continuation("> - quoted"),
+            Some(Continuation::Next("> - ".to_string()))
+        );
+        assert_eq!(continuation("- "), Some(Continuation::End(String::new())));
+        assert_eq!(continuation("plain"), None);
+    }
+
+    #[test]
+    fn stops_at_a_number_that_would_overflow() {
+        assert_eq!(continuation(&format!("{}. last", u64::MAX)), None);
+    }
+
+    #[test]
+    fn nested_item_starts_a_new_list_and_closes_the_gap() {
+        let content = "1. a\n   2. b\n3. c";
+        assert_eq!(renumbered(content, 6), "1. a\n   1. b\n2. c");
+    }
+
+    #[test]
+    fn outdented_item_continues_the_outer_list() {
+        let content = "1. a\n   1. b\n   2. c\n4. d";
+        assert_eq!(renumbered(content, 0), "1. a\n   1. b\n   2. c\n2. d");
+    }
+
+    #[test]
+    fn top_level_list_keeps_its_first_number() {
+        let content = "5. a\n7. b";
+        assert_eq!(renumbered(content, 0), "5. a\n6. b");
+    }
+
+    #[test]
+    fn blank_line_separates_lists() {
+        let content = "1. a\n\n5. b";
+        assert_eq!(renumbered(content, 0), content);
+    }
+}
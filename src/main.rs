@@ -4,15 +4,33 @@
 //! key binding configuration, and window creation.
 
 mod autocomplete;
+mod column;
+mod commands;
+mod config;
+mod cursors;
 mod editor;
+mod file_ops;
+mod file_tree;
 mod find;
+mod folding;
+mod frecency;
+mod fuzzy;
+mod ignore;
+mod indent;
+mod lines;
+mod lists;
 mod markdown;
+mod outline;
 mod palette;
+mod selection;
+mod state;
+mod text_input;
+mod text_layout;
+mod workspace;
+mod wrap;
 
 use editor::TextEditor;
-use gpui::{
-    App, AppContext, Application, Bounds, KeyBinding, WindowBounds, WindowOptions, px, size,
-};
+use gpui::{App, AppContext, Application, Bounds, WindowBounds, WindowOptions, px, size};
 
 /// Application entry point.
 ///
@@ -34,37 +52,11 @@ fn main() {
     let file_path = args.get(1).cloned();
 
     Application::new().run(move |cx: &mut App| {
-        use editor::{
-            Backspace, Copy, Cut, Enter, FindNext, FindPrevious, MoveDown, MoveLeft, MoveRight,
-            MoveUp, Paste, Quit, Save, SelectAll, SelectDown, SelectLeft, SelectRight, SelectUp,
-            ToggleFind, TogglePalette,
-        };
-
         // Configure global keybindings for the application.
         // These bindings are active whenever the TextEditor has focus.
-        // Uses standard editor conventions (arrow keys, Ctrl+S, etc.)
-        cx.bind_keys([
-            KeyBinding::new("left", MoveLeft, None),
-            KeyBinding::new("right", MoveRight, None),
-            KeyBinding::new("up", MoveUp, None),
-            KeyBinding::new("down", MoveDown, None),
-            KeyBinding::new("backspace", Backspace, None),
-            KeyBinding::new("enter", Enter, None),
-            KeyBinding::new("ctrl-s", Save, None),
-            KeyBinding::new("ctrl-q", Quit, None),
-            KeyBinding::new("ctrl-c", Copy, None),
-            KeyBinding::new("ctrl-v", Paste, None),
-            KeyBinding::new("ctrl-x", Cut, None),
-            KeyBinding::new("shift-left", SelectLeft, None),
-            KeyBinding::new("shift-right", SelectRight, None),
-            KeyBinding::new("shift-up", SelectUp, None),
-            KeyBinding::new("shift-down", SelectDown, None),
-            KeyBinding::new("ctrl-a", SelectAll, None),
-            KeyBinding::new("ctrl-p", TogglePalette, None),
-            KeyBinding::new("ctrl-f", ToggleFind, None),
-            KeyBinding::new("f3", FindNext, None),
-            KeyBinding::new("shift-f3", FindPrevious, None),
-        ]);
+        // The command table in `commands.rs` is the single source of truth so the
+        // command palette always shows the shortcuts that are actually bound.
+        cx.bind_keys(commands::key_bindings());
 
         // Create a centered window with fixed dimensions (800x600).
         // Consider making window size configurable via config file in future iterations.
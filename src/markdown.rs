@@ -45,6 +45,19 @@ pub enum MarkdownToken {
     Normal,
 }
 
+/// A heading in a document, as listed by the outline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heading {
+    /// Heading level (1-6)
+    pub level: usize,
+    /// Heading text without the leading `#` markers
+    pub title: String,
+    /// Byte offset of the start of the heading line
+    pub offset: usize,
+    /// Zero-based line number
+    pub line: usize,
+}
+
 /// Stateless markdown syntax highlighter.
 ///
 /// Provides line-based tokenization and color mapping.
@@ -121,23 +134,8 @@ impl MarkdownHighlighter {
         let mut tokens = Vec::new();
 
         // Line-level patterns: headings (H1-H6)
-        if line.starts_with("# ") {
-            tokens.push((line.to_string(), MarkdownToken::Heading(1)));
-            return tokens;
-        } else if line.starts_with("## ") {
-            tokens.push((line.to_string(), MarkdownToken::Heading(2)));
-            return tokens;
-        } else if line.starts_with("### ") {
-            tokens.push((line.to_string(), MarkdownToken::Heading(3)));
-            return tokens;
-        } else if line.starts_with("#### ") {
-            tokens.push((line.to_string(), MarkdownToken::Heading(4)));
-            return tokens;
-        } else if line.starts_with("##### ") {
-            tokens.push((line.to_string(), MarkdownToken::Heading(5)));
-            return tokens;
-        } else if line.starts_with("###### ") {
-            tokens.push((line.to_string(), MarkdownToken::Heading(6)));
+        if let Some(level) = Self::heading_level(line) {
+            tokens.push((line.to_string(), MarkdownToken::Heading(level)));
             return tokens;
         }
 
@@ -272,4 +270,70 @@ impl MarkdownHighlighter {
 
         tokens
     }
+
+    /// Returns the level of an ATX heading line such as `## Title`.
+    fn heading_level(line: &str) -> Option<usize> {
+        let level = line.bytes().take_while(|&b| b == b'#').count();
+        ((1..=6).contains(&level) && line[level..].starts_with(' ')).then_some(level)
+    }
+
+    /// Lists the headings of a document in order, skipping fenced code blocks.
+    pub fn headings(content: &str) -> Vec<Heading> {
+        let mut headings = Vec::new();
+        let mut in_code_block = false;
+        let mut offset = 0;
+
+        for (line_number, line) in content.split('\n').enumerate() {
+            if line.starts_with("```") {
+                in_code_block = !in_code_block;
+            }
+            let level = if in_code_block {
+                None
+            } else {
+                Self::heading_level(line)
+            };
+            if let Some(level) = level {
+                headings.push(Heading {
+                    level,
+                    title: line[level..]
+                        .trim()
+                        .trim_end_matches('#')
+                        .trim_end()
+                        .to_string(),
+                    offset,
+                    line: line_number,
+                });
+            }
+            offset += line.len() + 1;
+        }
+
+        headings
+    }
+
+    /// Byte offset where the text of a line starts: after its indentation and
+    /// any list marker (with checkbox) or blockquote prefix.
+    pub fn text_start(line: &str) -> usize {
+        let leading = line.len() - line.trim_start_matches([' ', '\t']).len();
+        let rest = &line[leading..];
+
+        let marker = if let Some(quoted) = rest.strip_prefix('>') {
+            1 + quoted.len() - quoted.trim_start_matches(' ').len()
+        } else if ["- ", "* ", "+ "]
+            .iter()
+            .any(|bullet| rest.starts_with(bullet))
+        {
+            let checkbox = rest
+                .get(2..6)
+                .is_some_and(|text| matches!(text, "[ ] " | "[x] " | "[X] "));
+            if checkbox { 6 } else { 2 }
+        } else {
+            let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+            match rest.as_bytes().get(digits..digits + 2) {
+                Some([b'.' | b')', b' ']) if digits > 0 => digits + 2,
+                _ => 0,
+            }
+        };
+
+        leading + marker
+    }
 }
@@ -0,0 +1,279 @@
+//! Document outline: section structure derived from headings.
+//!
+//! A section is a heading plus everything up to the next heading of the same
+//! or a higher level, so it includes its body and all of its subsections. The
+//! functions here restructure a document by whole sections and return the new
+//! content together with where the caret should end up.
+
+use std::ops::Range;
+
+use gpui::{Context, Window, div, prelude::*, px, rgb};
+
+use crate::markdown::Heading;
+
+/// Width of the outline sidebar in pixels.
+pub const OUTLINE_WIDTH: f32 = 220.0;
+
+/// Result of restructuring a document.
+pub struct OutlineEdit {
+    /// The complete new document
+    pub content: String,
+    /// Caret position in the new document
+    pub cursor: usize,
+}
+
+/// Returns the index of the heading whose section contains `offset`.
+pub fn section_at(headings: &[Heading], offset: usize) -> Option<usize> {
+    headings
+        .iter()
+        .rposition(|heading| heading.offset <= offset)
+}
+
+/// Byte range of the section started by `headings[index]`.
+pub fn section_range(content: &str, headings: &[Heading], index: usize) -> Range<usize> {
+    let heading = &headings[index];
+    let end = headings[index + 1..]
+        .iter()
+        .find(|next| next.level <= heading.level)
+        .map(|next| next.offset)
+        .unwrap_or(content.len());
+    heading.offset..end
+}
+
+/// Swaps a section with its previous (`direction < 0`) or next sibling.
+///
+/// Siblings share the heading level and parent section; a section never moves
+/// out of its parent. Returns `None` when there is no sibling in that direction.
+pub fn move_section(
+    content: &str,
+    headings: &[Heading],
+    index: usize,
+    direction: isize,
+    cursor: usize,
+) -> Option<OutlineEdit> {
+    let level = headings[index].level;
+    let range = section_range(content, headings, index);
+
+    let insert_at = if direction < 0 {
+        let previous = headings[..index]
+            .iter()
+            .rev()
+            .take_while(|heading| heading.level >= level)
+            .find(|heading| heading.level == level)?;
+        previous.offset
+    } else {
+        let next = headings[index + 1..]
+            .iter()
+            .position(|heading| heading.level <= level)
+            .map(|position| index + 1 + position)
+            .filter(|&next| headings[next].level == level)?;
+        section_range(content, headings, next).end
+    };
+
+    move_range(content, range, insert_at, cursor)
+}
+
+/// Moves the section of `headings[index]` before `headings[target]`, or to the
+/// end of the document when `target` is `None`.
+///
+/// Returns `None` when the drop would not change anything or would put the
+/// section inside itself.
+pub fn move_section_to(
+    content: &str,
+    headings: &[Heading],
+    index: usize,
+    target: Option<usize>,
+    cursor: usize,
+) -> Option<OutlineEdit> {
+    let range = section_range(content, headings, index);
+    let insert_at = target
+        .map(|target| headings[target].offset)
+        .unwrap_or(content.len());
+    move_range(content, range, insert_at, cursor)
+}
+
+/// Changes the level of a heading and all headings nested in its section by `delta`.
+///
+/// Returns `None` when any heading would leave the 1-6 range.
+pub fn shift_section_levels(
+    content: &str,
+    headings: &[Heading],
+    index: usize,
+    delta: isize,
+    cursor: usize,
+) -> Option<OutlineEdit> {
+    let range = section_range(content, headings, index);
+    let nested: Vec<&Heading> = headings
+        .iter()
+        .filter(|heading| range.contains(&heading.offset))
+        .collect();
+    let in_bounds = nested.iter().all(|heading| {
+        let level = heading.level as isize + delta;
+        (1..=6).contains(&level)
+    });
+    if !in_bounds {
+        return None;
+    }
+
+    let mut text = content.to_string();
+    let mut new_cursor = cursor as isize;
+    // Edit back to front so earlier offsets stay valid
+    for heading in nested.iter().rev() {
+        let level = (heading.level as isize + delta) as usize;
+        text.replace_range(
+            heading.offset..heading.offset + heading.level,
+            &"#".repeat(level),
+        );
+        if heading.offset + heading.level <= cursor {
+            new_cursor += delta;
+        }
+    }
+
+    Some(OutlineEdit {
+        cursor: (new_cursor.max(0) as usize).min(text.len()),
+        content: text,
+    })
+}
+
+/// Cuts `range` out of `content` and reinserts it at `insert_at` (an offset in the original).
+fn move_range(
+    content: &str,
+    range: Range<usize>,
+    insert_at: usize,
+    cursor: usize,
+) -> Option<OutlineEdit> {
+    if insert_at >= range.start && insert_at <= range.end {
+        return None;
+    }
+
+    // Sections end at a line break, except the last one; add it temporarily
+    let mut text = content.to_string();
+    let missing_newline = !text.ends_with('\n');
+    let end_of_text =
+        |offset: usize| offset + usize::from(missing_newline && offset == content.len());
+    if missing_newline {
+        text.push('\n');
+    }
+    let range = range.start..end_of_text(range.end);
+    let insert_at = end_of_text(insert_at);
+
+    let section: String = text.drain(range.clone()).collect();
+    let len = section.len();
+    let insert_at = if insert_at > range.start {
+        insert_at - len
+    } else {
+        insert_at
+    };
+    text.insert_str(insert_at, &section);
+    if missing_newline {
+        text.pop();
+    }
+
+    let cursor = if range.contains(&cursor) {
+        insert_at + (cursor - range.start)
+    } else {
+        let mut cursor = if cursor >= range.end {
+            cursor - len
+        } else {
+            cursor
+        };
+        if cursor >= insert_at {
+            cursor += len;
+        }
+        cursor
+    };
+
+    Some(OutlineEdit {
+        cursor: cursor.min(text.len()),
+        content: text,
+    })
+}
+
+/// Drag payload for reordering sections in the outline sidebar.
+#[derive(Clone, Debug)]
+pub struct DraggedSection {
+    /// Index of the dragged heading in the document's heading list
+    pub index: usize,
+    pub title: String,
+}
+
+impl Render for DraggedSection {
+    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+        div()
+            .px_2()
+            .py_1()
+            .bg(rgb(0x094771))
+            .border_1()
+            .border_color(rgb(0x569CD6))
+            .rounded_sm()
+            .text_sm()
+            .font_family("monospace")
+            .text_color(rgb(0xffffff))
+            .max_w(px(OUTLINE_WIDTH))
+            .child(self.title.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markdown::MarkdownHighlighter;
+
+    const DOC: &str = "# A\na\n## A1\n# B\nb";
+
+    fn headings(content: &str) -> Vec<Heading> {
+        MarkdownHighlighter::headings(content)
+    }
+
+    #[test]
+    fn section_includes_its_subsections() {
+        let headings = headings(DOC);
+        assert_eq!(section_range(DOC, &headings, 0), 0..12);
+        assert_eq!(section_range(DOC, &headings, 1), 6..12);
+        assert_eq!(section_range(DOC, &headings, 2), 12..DOC.len());
+    }
+
+    #[test]
+    fn finds_the_section_containing_an_offset() {
+        let headings = headings(DOC);
+        assert_eq!(section_at(&headings, 0), Some(0));
+        assert_eq!(section_at(&headings, 8), Some(1));
+        assert_eq!(section_at(&headings, DOC.len()), Some(2));
+        assert_eq!(
+            section_at(&MarkdownHighlighter::headings("text\n# A"), 0),
+            None
+        );
+    }
+
+    #[test]
+    fn swaps_sibling_sections_of_a_document_without_a_final_newline() {
+        let headings = headings(DOC);
+        let edit = move_section(DOC, &headings, 2, -1, 16).unwrap();
+        assert_eq!(edit.content, "# B\nb\n# A\na\n## A1");
+        assert_eq!(edit.cursor, 4);
+    }
+
+    #[test]
+    fn subsection_stays_in_its_parent() {
+        let headings = headings(DOC);
+        assert!(move_section(DOC, &headings, 1, -1, 6).is_none());
+        assert!(move_section(DOC, &headings, 1, 1, 6).is_none());
+    }
+
+    #[test]
+    fn drops_a_section_at_the_end() {
+        let headings = headings(DOC);
+        let edit = move_section_to(DOC, &headings, 0, None, 0).unwrap();
+        assert_eq!(edit.content, "# B\nb\n# A\na\n## A1");
+        assert!(move_section_to(DOC, &headings, 0, Some(1), 0).is_none());
+    }
+
+    #[test]
+    fn shifts_nested_levels_within_bounds() {
+        let headings = headings(DOC);
+        let edit = shift_section_levels(DOC, &headings, 0, 1, 3).unwrap();
+        assert_eq!(edit.content, "## A\na\n### A1\n# B\nb");
+        assert_eq!(edit.cursor, 4);
+        assert!(shift_section_levels(DOC, &headings, 0, -1, 0).is_none());
+    }
+}
@@ -1,9 +1,26 @@
 //! Command palette for fuzzy file finding and quick navigation.
+//!
+//! The first character of the query selects the mode: a plain query searches
+//! files, a `>` prefix searches editor commands, an `@` prefix searches the
+//! headings of the current buffer, and a `#` prefix searches the headings of
+//! every markdown file in the workspace.
 
 use gpui::{
-    App, Context, FocusHandle, Focusable, KeyDownEvent, Render, Window, div, prelude::*, px, rgb,
+    Action, App, BackgroundExecutor, Context, Entity, FocusHandle, Focusable, FontWeight,
+    KeyDownEvent, Render, Rgba, Subscription, Window, div, prelude::*, px, rgb,
 };
-use std::path::{Path, PathBuf};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use crate::commands::Command;
+use crate::file_ops::FileOperation;
+use crate::frecency::FileUsage;
+use crate::fuzzy::Matcher;
+use crate::markdown::Heading;
+use crate::text_input::{InputResponse, TextInput};
+use crate::workspace::{FileKind, WorkspaceIndex};
 
 /// Represents a file entry in the palette with fuzzy match score.
 #[derive(Clone, Debug)]
@@ -12,218 +29,819 @@ pub struct FileEntry {
     pub path: PathBuf,
     /// Display name (relative to working directory)
     pub display_name: String,
+    /// Document type, shown as a badge
+    pub kind: FileKind,
+    /// Fuzzy match score (higher is better, None if no match)
+    pub score: Option<i32>,
+    /// Char indices of `display_name` matched by the query
+    pub matched_indices: Vec<usize>,
+    /// Headings cached by the workspace index
+    pub headings: Arc<[Heading]>,
+}
+
+/// Represents an editor command in the palette with fuzzy match score.
+#[derive(Clone)]
+pub struct CommandEntry {
+    /// Command metadata and action constructor
+    pub command: Command,
+    /// Fuzzy match score (higher is better, None if no match)
+    pub score: Option<i32>,
+    /// Char indices of the command name matched by the query
+    pub matched_indices: Vec<usize>,
+}
+
+/// Represents a heading of the current buffer in the palette with fuzzy match score.
+#[derive(Clone, Debug)]
+pub struct SymbolEntry {
+    /// Heading location and text
+    pub heading: Heading,
+    /// Fuzzy match score (higher is better, None if no match)
+    pub score: Option<i32>,
+    /// Char indices of the heading title matched by the query
+    pub matched_indices: Vec<usize>,
+}
+
+/// A heading of a workspace file in the palette with fuzzy match score.
+#[derive(Clone, Debug)]
+pub struct WorkspaceSymbolEntry {
+    /// Index into the palette's file list
+    file: usize,
+    /// Index into that file's headings
+    heading: usize,
     /// Fuzzy match score (higher is better, None if no match)
     pub score: Option<i32>,
+    /// Char indices of the search text (title, a space, then the file path) matched by the query
+    pub matched_indices: Vec<usize>,
+}
+
+/// Searchable text of one workspace heading: its title followed by the file path,
+/// so a query can mention both, e.g. "deploy rollback".
+struct HeadingSearchText {
+    file: usize,
+    heading: usize,
+    text: String,
+}
+
+/// Maximum number of result rows shown at once; the list scrolls beyond this.
+const MAX_VISIBLE_ITEMS: usize = 10;
+
+/// Extension given to new files when the typed name has none.
+pub const DEFAULT_NEW_FILE_EXTENSION: &str = "md";
+
+/// Frecency is divided by this before being added to a fuzzy match score.
+const FRECENCY_BONUS_DIVISOR: u32 = 4;
+
+/// Upper bound for the frecency bonus so history never hides a much better match.
+const FRECENCY_BONUS_CAP: i32 = 60;
+
+/// Editor state captured when the palette opens.
+pub struct PaletteContext {
+    /// Open history of the workspace, for ranking files
+    pub usage: HashMap<PathBuf, FileUsage>,
+    /// File shown in the editor, marked in the results
+    pub current_file: Option<PathBuf>,
+    /// Headings of the current buffer, for symbol mode
+    pub headings: Vec<Heading>,
+}
+
+/// One rendered result row.
+struct ResultRow {
+    /// Main text
+    label: String,
+    /// Char indices of `label` to highlight
+    label_matches: Vec<usize>,
+    /// Dimmed text after the label, such as the parent directory
+    context: String,
+    /// Char indices of `context` to highlight
+    context_matches: Vec<usize>,
+    /// Right-aligned secondary text
+    detail: String,
+    /// Small colored tag shown before the label
+    badge: Option<(String, Rgba)>,
+    /// Nesting depth, rendered as left padding
+    indent: usize,
 }
 
-/// Command palette for fuzzy file finding.
+impl ResultRow {
+    /// A row with just a label.
+    fn plain(label: &str) -> Self {
+        Self {
+            label: label.to_string(),
+            label_matches: Vec::new(),
+            context: String::new(),
+            context_matches: Vec::new(),
+            detail: String::new(),
+            badge: None,
+            indent: 0,
+        }
+    }
+}
+
+/// What the palette is searching, selected by the query prefix.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PaletteMode {
+    /// Plain query: documents in the working directory.
+    Files,
+    /// `>` prefix: editor commands.
+    Commands,
+    /// `@` prefix: headings of the current buffer.
+    Symbols,
+    /// `#` prefix: headings of every markdown file in the workspace.
+    WorkspaceSymbols,
+}
+
+impl PaletteMode {
+    /// Splits a raw query into its mode and the text to match.
+    fn parse(query: &str) -> (Self, &str) {
+        if let Some(rest) = query.strip_prefix('>') {
+            (PaletteMode::Commands, rest.trim_start())
+        } else if let Some(rest) = query.strip_prefix('@') {
+            (PaletteMode::Symbols, rest.trim_start())
+        } else if let Some(rest) = query.strip_prefix('#') {
+            (PaletteMode::WorkspaceSymbols, rest.trim_start())
+        } else {
+            (PaletteMode::Files, query)
+        }
+    }
+}
+
+/// Secondary actions offered for a file in the results.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FileAction {
+    Rename,
+    Move,
+    Duplicate,
+    Delete,
+}
+
+impl FileAction {
+    const ALL: [FileAction; 4] = [
+        FileAction::Rename,
+        FileAction::Move,
+        FileAction::Duplicate,
+        FileAction::Delete,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            FileAction::Rename => "Rename",
+            FileAction::Move => "Move",
+            FileAction::Duplicate => "Duplicate",
+            FileAction::Delete => "Delete (move to trash)",
+        }
+    }
+
+    /// Text shown before the destination input.
+    fn prompt(self) -> &'static str {
+        match self {
+            FileAction::Rename => "Rename to",
+            FileAction::Move => "Move to",
+            FileAction::Duplicate => "Duplicate as",
+            FileAction::Delete => "Delete",
+        }
+    }
+}
+
+/// Action menu for one file, opened with Ctrl+Enter on a result.
+struct FileActionMenu {
+    /// File the actions apply to
+    path: PathBuf,
+    /// Path relative to the workspace root
+    display_name: String,
+    /// Highlighted action while choosing
+    selected: usize,
+    /// Chosen action and the destination being typed for it; for a delete,
+    /// the pending confirmation
+    destination: Option<(FileAction, TextInput)>,
+}
+
+/// Command palette for fuzzy file finding and command execution.
 ///
-/// Provides a modal overlay for searching and opening markdown files.
+/// Provides a modal overlay for searching and opening workspace documents.
 /// Uses a custom fuzzy matching algorithm with no external dependencies.
 pub struct Palette {
     /// Current search query entered by user
-    query: String,
-    /// All markdown files found in working directory
+    query: TextInput,
+    /// Mode derived from the query prefix
+    mode: PaletteMode,
+    /// Shared workspace file index
+    workspace: Entity<WorkspaceIndex>,
+    /// Workspace root, for resolving typed paths
+    root: PathBuf,
+    /// Keeps the file list in sync with the index while the palette is open
+    _workspace_subscription: Subscription,
+    /// Spreads fuzzy matching of long lists over background threads
+    executor: BackgroundExecutor,
+    /// True while the index is still streaming its initial scan
+    scanning: bool,
+    /// All documents found in working directory
     all_files: Vec<FileEntry>,
+    /// Open history used to rank files
+    usage: HashMap<PathBuf, FileUsage>,
+    /// File shown in the editor
+    current_file: Option<PathBuf>,
     /// Filtered and ranked files based on current query
     filtered_files: Vec<FileEntry>,
+    /// All registered editor commands
+    all_commands: Vec<Command>,
+    /// Filtered and ranked commands based on current query
+    filtered_commands: Vec<CommandEntry>,
+    /// Headings of the current buffer
+    all_headings: Vec<Heading>,
+    /// Filtered and ranked headings based on current query
+    filtered_headings: Vec<SymbolEntry>,
+    /// Search text of every workspace heading, built on first use after each reload
+    heading_search_texts: Option<Vec<HeadingSearchText>>,
+    /// Filtered and ranked workspace headings based on current query
+    filtered_workspace_headings: Vec<WorkspaceSymbolEntry>,
     /// Currently selected index in filtered results
     selected_index: usize,
+    /// Rename/move/duplicate/delete menu for the selected file, when open
+    file_actions: Option<FileActionMenu>,
+    /// Index of the first visible result row
+    scroll_top: usize,
     /// GPUI focus handle for keyboard event routing
     focus_handle: FocusHandle,
     /// Flag indicating if user pressed Enter to select a file
     pub should_open: bool,
     /// Flag indicating if user pressed Escape to close
     pub should_close: bool,
+    /// File operation confirmed by the user, for the editor to apply
+    pub operation: Option<FileOperation>,
+    /// Why the last operation failed, shown until the next key
+    error: Option<String>,
 }
 
 impl Palette {
-    /// Creates a new Palette instance and scans for markdown files.
+    /// Creates a new Palette instance backed by the shared workspace index.
     ///
     /// # Arguments
     ///
-    /// * `working_dir` - Directory to scan for .md files
+    /// * `workspace` - Index providing the documents to search
+    /// * `initial_query` - Prefilled query, e.g. `">"` to start in command mode
+    /// * `context` - Open history and current file from the editor
     /// * `cx` - GPUI context for initialization
-    pub fn new(working_dir: PathBuf, cx: &mut Context<Self>) -> Self {
-        let all_files = Self::scan_markdown_files(&working_dir);
-        let filtered_files = all_files.clone();
+    pub fn new(
+        workspace: Entity<WorkspaceIndex>,
+        initial_query: &str,
+        context: PaletteContext,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let subscription = cx.observe(&workspace, |palette, _, cx| {
+            palette.reload_files(cx);
+            cx.notify();
+        });
 
-        Self {
-            query: String::new(),
-            all_files,
-            filtered_files,
+        let root = workspace.read(cx).root().to_path_buf();
+        let mut palette = Self {
+            query: TextInput::new(initial_query),
+            mode: PaletteMode::Files,
+            workspace,
+            root,
+            _workspace_subscription: subscription,
+            executor: cx.background_executor().clone(),
+            scanning: false,
+            filtered_files: Vec::new(),
+            all_files: Vec::new(),
+            usage: context.usage,
+            current_file: context.current_file,
+            all_commands: crate::commands::all(),
+            filtered_commands: Vec::new(),
+            all_headings: context.headings,
+            filtered_headings: Vec::new(),
+            heading_search_texts: None,
+            filtered_workspace_headings: Vec::new(),
             selected_index: 0,
+            file_actions: None,
+            scroll_top: 0,
             focus_handle: cx.focus_handle(),
             should_open: false,
             should_close: false,
-        }
+            operation: None,
+            error: None,
+        };
+        palette.update_filtered();
+        palette.reload_files(cx);
+        palette
     }
 
-    /// Scans the working directory recursively for .md files.
+    /// Rebuilds the file list from the workspace index.
     ///
-    /// Returns a vector of FileEntry with paths and display names.
-    /// Excludes hidden directories and files (starting with '.').
-    fn scan_markdown_files(dir: &Path) -> Vec<FileEntry> {
-        let mut files = Vec::new();
-
-        if let Ok(entries) = std::fs::read_dir(dir) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-
-                // Skip hidden files/directories
-                if let Some(name) = path.file_name() {
-                    if name.to_string_lossy().starts_with('.') {
-                        continue;
-                    }
-                }
+    /// Called on open and whenever the index streams new results or picks up
+    /// file system changes. Keeps the selected file selected when possible.
+    fn reload_files(&mut self, cx: &mut Context<Self>) {
+        let selected = self.get_selected_file();
 
-                if path.is_dir() {
-                    // Recursively scan subdirectories
-                    files.extend(Self::scan_markdown_files(&path));
-                } else if path.extension().and_then(|s| s.to_str()) == Some("md") {
-                    if let Ok(abs_path) = path.canonicalize() {
-                        let display_name = path
-                            .strip_prefix(dir)
-                            .unwrap_or(&path)
-                            .to_string_lossy()
-                            .to_string();
-
-                        files.push(FileEntry {
-                            path: abs_path,
-                            display_name,
-                            score: None,
-                        });
-                    }
-                }
+        let index = self.workspace.read(cx);
+        self.scanning = index.is_scanning();
+        self.all_files = index
+            .files()
+            .iter()
+            .map(|file| FileEntry {
+                path: file.path.clone(),
+                display_name: file.display_name.clone(),
+                kind: file.kind.clone(),
+                score: None,
+                matched_indices: Vec::new(),
+                headings: file.headings.clone(),
+            })
+            .collect();
+        self.heading_search_texts = None;
+
+        if self.mode == PaletteMode::WorkspaceSymbols {
+            self.update_filtered_workspace_headings();
+        } else if self.mode == PaletteMode::Files {
+            self.update_filtered_files();
+            let position = selected
+                .and_then(|selected| self.filtered_files.iter().position(|f| f.path == selected));
+            if let Some(idx) = position {
+                self.select(idx);
             }
         }
+    }
 
-        files
+    /// Re-parses the query prefix and refreshes the results for the active mode.
+    fn update_filtered(&mut self) {
+        let (mode, _) = PaletteMode::parse(self.query.text());
+        self.mode = mode;
+        match mode {
+            PaletteMode::Files => self.update_filtered_files(),
+            PaletteMode::Commands => self.update_filtered_commands(),
+            PaletteMode::Symbols => self.update_filtered_headings(),
+            PaletteMode::WorkspaceSymbols => self.update_filtered_workspace_headings(),
+        }
     }
 
-    /// Custom fuzzy matching algorithm.
-    ///
-    /// Scores a string based on how well it matches the query pattern.
-    /// Algorithm:
-    /// - Sequential character matching (all query chars must appear in order)
-    /// - Bonus points for consecutive matches
-    /// - Bonus points for matches at word boundaries (after '/', '-', '_', ' ')
-    /// - Case-insensitive matching
+    /// Updates the filtered file list based on current query.
     ///
-    /// Returns None if query doesn't match, otherwise returns score (higher is better).
-    fn fuzzy_match(query: &str, target: &str) -> Option<i32> {
+    /// Matches paths with the fuzzy matcher, filters out non-matches, and sorts by score
+    /// blended with frecency. An empty query lists recently opened files
+    /// first, most recent on top, followed by the rest in index order.
+    fn update_filtered_files(&mut self) {
+        let (_, query) = PaletteMode::parse(self.query.text());
+
         if query.is_empty() {
-            return Some(0);
+            self.filtered_files = self.all_files.clone();
+            // Stable sort keeps index order for files never opened
+            self.filtered_files.sort_by_key(|file| {
+                Reverse(self.usage.get(&file.path).map(|usage| usage.last_opened))
+            });
+            self.select(0);
+            return;
         }
 
-        let query_lower = query.to_lowercase();
-        let target_lower = target.to_lowercase();
-        let query_chars: Vec<char> = query_lower.chars().collect();
-        let target_chars: Vec<char> = target_lower.chars().collect();
+        let matcher = Matcher::new(query).with_file_name_bonus();
+        self.filtered_files = matcher
+            .match_all(&self.executor, &self.all_files, |file| {
+                file.display_name.as_str()
+            })
+            .into_iter()
+            .map(|(idx, score, matched_indices)| {
+                let file = &self.all_files[idx];
+                FileEntry {
+                    path: file.path.clone(),
+                    display_name: file.display_name.clone(),
+                    kind: file.kind.clone(),
+                    score: Some(score + self.frecency_bonus(&file.path)),
+                    matched_indices,
+                    headings: file.headings.clone(),
+                }
+            })
+            .collect();
 
-        let mut score = 0;
-        let mut query_idx = 0;
-        let mut consecutive_matches = 0;
+        // Sort by score (highest first), preferring shorter paths on ties
+        self.filtered_files.sort_by(|a, b| {
+            b.score
+                .unwrap_or(0)
+                .cmp(&a.score.unwrap_or(0))
+                .then_with(|| a.display_name.len().cmp(&b.display_name.len()))
+        });
 
-        for (target_idx, &target_char) in target_chars.iter().enumerate() {
-            if query_idx >= query_chars.len() {
-                break;
-            }
+        // Reset selection to first item
+        self.select(0);
+    }
 
-            if target_char == query_chars[query_idx] {
-                // Base score for match
-                score += 10;
+    /// Ranking bonus for files the user opens often and recently.
+    fn frecency_bonus(&self, path: &Path) -> i32 {
+        self.usage
+            .get(path)
+            .map(|usage| ((usage.frecency / FRECENCY_BONUS_DIVISOR) as i32).min(FRECENCY_BONUS_CAP))
+            .unwrap_or(0)
+    }
 
-                // Bonus for consecutive matches
-                consecutive_matches += 1;
-                score += consecutive_matches * 5;
+    /// Updates the filtered command list based on current query.
+    ///
+    /// Commands keep their registration order when the query is empty.
+    fn update_filtered_commands(&mut self) {
+        let (_, query) = PaletteMode::parse(self.query.text());
+        self.filtered_commands = Matcher::new(query)
+            .match_all(&self.executor, &self.all_commands, |command| command.name)
+            .into_iter()
+            .map(|(idx, score, matched_indices)| CommandEntry {
+                command: self.all_commands[idx],
+                score: Some(score),
+                matched_indices,
+            })
+            .collect();
 
-                // Bonus for match at start of string
-                if target_idx == 0 {
-                    score += 20;
-                }
+        // Stable sort keeps registration order among equal scores
+        sort_by_score(&mut self.filtered_commands, |entry| entry.score);
 
-                // Bonus for match after word boundary
-                if target_idx > 0 {
-                    let prev_char = target_chars[target_idx - 1];
-                    if prev_char == '/' || prev_char == '-' || prev_char == '_' || prev_char == ' '
-                    {
-                        score += 15;
-                    }
-                }
+        self.select(0);
+    }
 
-                query_idx += 1;
-            } else {
-                consecutive_matches = 0;
-            }
-        }
+    /// Updates the filtered heading list based on current query.
+    ///
+    /// Headings keep document order when the query is empty.
+    fn update_filtered_headings(&mut self) {
+        let (_, query) = PaletteMode::parse(self.query.text());
+        self.filtered_headings = Matcher::new(query)
+            .match_all(&self.executor, &self.all_headings, |heading| {
+                heading.title.as_str()
+            })
+            .into_iter()
+            .map(|(idx, score, matched_indices)| SymbolEntry {
+                heading: self.all_headings[idx].clone(),
+                score: Some(score),
+                matched_indices,
+            })
+            .collect();
 
-        // Check if all query characters were matched
-        if query_idx == query_chars.len() {
-            Some(score)
-        } else {
-            None
-        }
+        // Stable sort keeps document order among equal scores
+        sort_by_score(&mut self.filtered_headings, |entry| entry.score);
+
+        self.select(0);
     }
 
-    /// Updates the filtered file list based on current query.
+    /// Updates the filtered workspace heading list based on current query.
     ///
-    /// Applies fuzzy matching, filters out non-matches, and sorts by score.
-    fn update_filtered_files(&mut self) {
-        self.filtered_files = self
-            .all_files
-            .iter()
-            .filter_map(|file| {
-                let score = Self::fuzzy_match(&self.query, &file.display_name);
-                score.map(|s| FileEntry {
-                    path: file.path.clone(),
-                    display_name: file.display_name.clone(),
-                    score: Some(s),
+    /// Headings keep file and document order when the query is empty.
+    fn update_filtered_workspace_headings(&mut self) {
+        let (_, query) = PaletteMode::parse(self.query.text());
+        let all_files = &self.all_files;
+        let texts: &[HeadingSearchText] = self.heading_search_texts.get_or_insert_with(|| {
+            all_files
+                .iter()
+                .enumerate()
+                .flat_map(|(file_idx, file)| {
+                    file.headings
+                        .iter()
+                        .enumerate()
+                        .map(move |(heading_idx, heading)| HeadingSearchText {
+                            file: file_idx,
+                            heading: heading_idx,
+                            text: format!("{} {}", heading.title, file.display_name),
+                        })
                 })
+                .collect()
+        });
+
+        self.filtered_workspace_headings = Matcher::new(query)
+            .match_all(&self.executor, texts, |entry| entry.text.as_str())
+            .into_iter()
+            .map(|(idx, score, matched_indices)| WorkspaceSymbolEntry {
+                file: texts[idx].file,
+                heading: texts[idx].heading,
+                score: Some(score),
+                matched_indices,
             })
             .collect();
 
-        // Sort by score (highest first)
-        self.filtered_files
-            .sort_by(|a, b| b.score.unwrap_or(0).cmp(&a.score.unwrap_or(0)));
+        // Stable sort keeps file and document order among equal scores
+        sort_by_score(&mut self.filtered_workspace_headings, |entry| entry.score);
 
-        // Reset selection to first item
-        self.selected_index = 0;
+        self.select(0);
+    }
+
+    /// Builds a row for a workspace heading: the title, then its file dimmed.
+    fn workspace_heading_row(&self, entry: &WorkspaceSymbolEntry) -> ResultRow {
+        let file = &self.all_files[entry.file];
+        let heading = &file.headings[entry.heading];
+        let path_offset = heading.title.chars().count() + 1;
+
+        ResultRow {
+            label: heading.title.clone(),
+            label_matches: entry
+                .matched_indices
+                .iter()
+                .copied()
+                .filter(|&idx| idx < path_offset)
+                .collect(),
+            context: file.display_name.clone(),
+            context_matches: entry
+                .matched_indices
+                .iter()
+                .filter(|&&idx| idx >= path_offset)
+                .map(|&idx| idx - path_offset)
+                .collect(),
+            detail: format!("line {}", heading.line + 1),
+            badge: Some((format!("H{}", heading.level), rgb(0x569CD6))),
+            indent: 0,
+        }
+    }
+
+    /// Builds a result row showing the file name first and its parent directory dimmed.
+    fn file_row(&self, file: &FileEntry) -> ResultRow {
+        let display = file.display_name.as_str();
+        let (parent, name) = match display.rfind(std::path::MAIN_SEPARATOR) {
+            Some(idx) => (&display[..idx], &display[idx + 1..]),
+            None => ("", display),
+        };
+
+        // Match indices refer to chars of the full display name
+        let parent_chars = parent.chars().count();
+        let name_offset = if parent.is_empty() {
+            0
+        } else {
+            parent_chars + 1
+        };
+
+        ResultRow {
+            label: name.to_string(),
+            label_matches: file
+                .matched_indices
+                .iter()
+                .filter(|&&idx| idx >= name_offset)
+                .map(|&idx| idx - name_offset)
+                .collect(),
+            context: parent.to_string(),
+            context_matches: file
+                .matched_indices
+                .iter()
+                .copied()
+                .filter(|&idx| idx < parent_chars)
+                .collect(),
+            detail: if self.current_file.as_ref() == Some(&file.path) {
+                "● open".to_string()
+            } else {
+                String::new()
+            },
+            badge: Some((file.kind.badge(), Self::badge_color(&file.kind))),
+            indent: 0,
+        }
+    }
+
+    /// Renders `text` with the chars at `matches` emphasized.
+    fn highlighted_text(text: &str, matches: &[usize], color: Rgba) -> gpui::Div {
+        let mut row = div().flex().flex_row().text_color(color);
+        let mut run = String::new();
+        let mut run_matched = false;
+
+        for (idx, c) in text.chars().enumerate() {
+            let matched = matches.contains(&idx);
+            if matched != run_matched && !run.is_empty() {
+                row = row.child(Self::highlight_run(std::mem::take(&mut run), run_matched));
+            }
+            run_matched = matched;
+            run.push(c);
+        }
+        if !run.is_empty() {
+            row = row.child(Self::highlight_run(run, run_matched));
+        }
+
+        row
+    }
+
+    fn highlight_run(text: String, matched: bool) -> gpui::Div {
+        let run = div().child(text);
+        if matched {
+            run.text_color(rgb(0xF8C555)).font_weight(FontWeight::BOLD)
+        } else {
+            run
+        }
+    }
+
+    /// Badge color for a document type.
+    fn badge_color(kind: &FileKind) -> Rgba {
+        match kind {
+            kind if kind.is_markdown() => rgb(0x569CD6),
+            FileKind::Extension(ext) if ext == "org" => rgb(0x6A9955),
+            FileKind::Extension(ext) if ext == "todo" => rgb(0xCE9178),
+            _ => rgb(0x808080),
+        }
+    }
+
+    /// Number of rows in the active result list.
+    fn result_count(&self) -> usize {
+        match self.mode {
+            PaletteMode::Files => self.filtered_files.len(),
+            PaletteMode::Commands => self.filtered_commands.len(),
+            PaletteMode::Symbols => self.filtered_headings.len(),
+            PaletteMode::WorkspaceSymbols => self.filtered_workspace_headings.len(),
+        }
     }
 
     /// Returns the currently selected file path, if any.
     pub fn get_selected_file(&self) -> Option<PathBuf> {
+        if self.mode != PaletteMode::Files {
+            return None;
+        }
         self.filtered_files
             .get(self.selected_index)
             .map(|f| f.path.clone())
     }
 
-    /// Handles character input for search query.
-    fn handle_char_input(&mut self, c: char, cx: &mut Context<Self>) {
-        self.query.push(c);
-        self.update_filtered_files();
+    /// Returns the action for the currently selected command, if any.
+    pub fn get_selected_command(&self) -> Option<Box<dyn Action>> {
+        if self.mode != PaletteMode::Commands {
+            return None;
+        }
+        self.filtered_commands
+            .get(self.selected_index)
+            .map(|entry| (entry.command.action)())
+    }
+
+    /// Path offered for creation when the file query matches nothing.
+    ///
+    /// A name without an extension gets `.md`; parent directories in the
+    /// name are created along with the file. Nothing is offered while the
+    /// workspace is still being scanned, since the file may just not be
+    /// indexed yet, or for absolute names and names containing `..`, which
+    /// could point outside the workspace.
+    fn create_target(&self) -> Option<PathBuf> {
+        if self.mode != PaletteMode::Files || self.scanning || !self.filtered_files.is_empty() {
+            return None;
+        }
+        let name = self.query.text().trim();
+        let inside_root = Path::new(name)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+        if name.is_empty() || !inside_root {
+            return None;
+        }
+        let mut path = self.root.join(name);
+        if path.extension().is_none() {
+            path.set_extension(DEFAULT_NEW_FILE_EXTENSION);
+        }
+        Some(path)
+    }
+
+    /// Confirms the selected row: opens a file, runs a command, or creates a file.
+    fn confirm(&mut self, cx: &mut Context<Self>) {
+        match self.create_target() {
+            Some(path) => self.operation = Some(FileOperation::Create(path)),
+            None => self.should_open = true,
+        }
         cx.notify();
     }
 
-    /// Handles backspace to delete last character from query.
-    fn handle_backspace(&mut self, cx: &mut Context<Self>) {
-        self.query.pop();
-        self.update_filtered_files();
+    /// Keeps the palette open with the reason the confirmed operation failed.
+    pub fn show_error(&mut self, message: String, cx: &mut Context<Self>) {
+        self.operation = None;
+        self.error = Some(message);
         cx.notify();
     }
 
+    /// Opens the action menu for the selected file.
+    fn open_file_actions(&mut self, cx: &mut Context<Self>) {
+        if self.mode != PaletteMode::Files {
+            return;
+        }
+        let Some(file) = self.filtered_files.get(self.selected_index) else {
+            return;
+        };
+        self.file_actions = Some(FileActionMenu {
+            path: file.path.clone(),
+            display_name: file.display_name.clone(),
+            selected: 0,
+            destination: None,
+        });
+        cx.notify();
+    }
+
+    /// Handles a key while the file action menu is open.
+    fn handle_file_action_key(&mut self, event: &KeyDownEvent, cx: &mut Context<Self>) {
+        let Some(menu) = &mut self.file_actions else {
+            return;
+        };
+
+        match event.keystroke.key.as_str() {
+            "escape" => {
+                // Step back from the destination input to the menu, then to the results
+                if menu.destination.take().is_none() {
+                    self.file_actions = None;
+                }
+            }
+            "enter" => self.confirm_file_action(),
+            "up" if menu.destination.is_none() => menu.selected = menu.selected.saturating_sub(1),
+            "down" if menu.destination.is_none() => {
+                menu.selected = (menu.selected + 1).min(FileAction::ALL.len() - 1)
+            }
+            _ => {
+                if let Some((action, input)) = &mut menu.destination
+                    && *action != FileAction::Delete
+                {
+                    input.handle_key_down(event, cx);
+                }
+            }
+        }
+        cx.notify();
+    }
+
+    /// Advances the action menu: picks an action, or turns the typed destination
+    /// (or a confirmed delete) into an operation.
+    fn confirm_file_action(&mut self) {
+        let Some(menu) = &mut self.file_actions else {
+            return;
+        };
+        let from = menu.path.clone();
+
+        let Some((action, input)) = &menu.destination else {
+            let action = FileAction::ALL[menu.selected];
+            let initial = match action {
+                FileAction::Rename => from
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                FileAction::Duplicate => copy_name(&menu.display_name),
+                FileAction::Move => menu.display_name.clone(),
+                // Deleting only asks for confirmation
+                FileAction::Delete => String::new(),
+            };
+            menu.destination = Some((action, TextInput::new(initial)));
+            return;
+        };
+
+        if *action == FileAction::Delete {
+            self.operation = Some(FileOperation::Trash(from));
+            return;
+        }
+        let typed = input.text().trim();
+        if typed.is_empty() {
+            return;
+        }
+        let action = *action;
+        let mut to = match action {
+            // Renames stay in the file's directory; other paths are relative to the root
+            FileAction::Rename => from.parent().unwrap_or(&self.root).join(typed),
+            _ => self.root.join(typed),
+        };
+        // Typing a directory moves the file into it under its current name
+        let into_dir = typed.ends_with(std::path::MAIN_SEPARATOR) || to.is_dir();
+        if into_dir && let Some(name) = from.file_name() {
+            to.push(name);
+        }
+
+        self.operation = Some(match action {
+            FileAction::Duplicate => FileOperation::Duplicate { from, to },
+            _ => FileOperation::Move { from, to },
+        });
+    }
+
+    /// Returns the byte offset of the currently selected heading, if any.
+    pub fn get_selected_heading(&self) -> Option<usize> {
+        if self.mode != PaletteMode::Symbols {
+            return None;
+        }
+        self.filtered_headings
+            .get(self.selected_index)
+            .map(|entry| entry.heading.offset)
+    }
+
+    /// Returns the file and zero-based line of the selected workspace heading, if any.
+    pub fn get_selected_workspace_heading(&self) -> Option<(PathBuf, usize)> {
+        if self.mode != PaletteMode::WorkspaceSymbols {
+            return None;
+        }
+        let entry = self.filtered_workspace_headings.get(self.selected_index)?;
+        let file = &self.all_files[entry.file];
+        Some((file.path.clone(), file.headings[entry.heading].line))
+    }
+
+    /// Forwards a key to the query input, refiltering when the text changes.
+    fn handle_query_key(&mut self, event: &KeyDownEvent, cx: &mut Context<Self>) -> bool {
+        let response = self.query.handle_key_down(event, cx);
+        if response == InputResponse::Edited {
+            self.update_filtered();
+        }
+        if response.handled() {
+            cx.notify();
+        }
+        response.handled()
+    }
+
+    /// Selects a result row, scrolling the list so it stays visible.
+    fn select(&mut self, index: usize) {
+        self.selected_index = index;
+        if index < self.scroll_top {
+            self.scroll_top = index;
+        } else if index >= self.scroll_top + MAX_VISIBLE_ITEMS {
+            self.scroll_top = index + 1 - MAX_VISIBLE_ITEMS;
+        }
+    }
+
     /// Handles up arrow to move selection up.
     fn handle_up(&mut self, cx: &mut Context<Self>) {
         if self.selected_index > 0 {
-            self.selected_index -= 1;
+            self.select(self.selected_index - 1);
             cx.notify();
         }
     }
 
     /// Handles down arrow to move selection down.
     fn handle_down(&mut self, cx: &mut Context<Self>) {
-        if self.selected_index < self.filtered_files.len().saturating_sub(1) {
-            self.selected_index += 1;
+        if self.selected_index < self.result_count().saturating_sub(1) {
+            self.select(self.selected_index + 1);
             cx.notify();
         }
     }
@@ -237,15 +855,160 @@ impl Focusable for Palette {
 
 impl Render for Palette {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
-        let max_visible_items = 10;
+        if let Some(menu) = &self.file_actions {
+            let (header, footer) = match &menu.destination {
+                Some((FileAction::Delete, _)) => (
+                    div().child(format!("Move {} to the trash?", menu.display_name)),
+                    "Enter to delete | Esc to go back",
+                ),
+                Some((action, input)) => (
+                    div()
+                        .flex()
+                        .flex_row()
+                        .gap_2()
+                        .child(format!("{}:", action.prompt()))
+                        .child(input.render("New name or path", true)),
+                    "Enter to confirm | Esc to go back",
+                ),
+                None => (
+                    div().child(format!("Actions for {}", menu.display_name)),
+                    "↑↓ navigate | Enter to choose | Esc to go back",
+                ),
+            };
+            let rows = if menu.destination.is_none() {
+                FileAction::ALL
+                    .iter()
+                    .map(|action| ResultRow::plain(action.label()))
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            return self.render_frame(header, rows, Some(menu.selected), footer.to_string(), cx);
+        }
+
+        let header = div()
+            .flex()
+            .flex_row()
+            .gap_2()
+            .child(">")
+            .child(self.query.render("Type to search...", true));
+
+        if let Some(path) = self.create_target() {
+            let name = path
+                .strip_prefix(&self.root)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .to_string();
+            let mut row = ResultRow::plain(&format!("Create {}", name));
+            row.detail = "new file".to_string();
+            let footer = "No matching files | Enter to create | Esc to close".to_string();
+            return self.render_frame(header, vec![row], Some(0), footer, cx);
+        }
+
+        let (rows, footer): (Vec<ResultRow>, String) = match self.mode {
+            PaletteMode::Files => (
+                self.filtered_files
+                    .iter()
+                    .skip(self.scroll_top)
+                    .take(MAX_VISIBLE_ITEMS)
+                    .map(|file| self.file_row(file))
+                    .collect(),
+                format!(
+                    "{} files{} | ↑↓ navigate | Enter to open | Ctrl+Enter actions | > commands | Esc to close",
+                    self.filtered_files.len(),
+                    if self.scanning { " (scanning…)" } else { "" }
+                ),
+            ),
+            PaletteMode::Commands => (
+                self.filtered_commands
+                    .iter()
+                    .skip(self.scroll_top)
+                    .take(MAX_VISIBLE_ITEMS)
+                    .map(|entry| ResultRow {
+                        label: entry.command.name.to_string(),
+                        label_matches: entry.matched_indices.clone(),
+                        context: String::new(),
+                        context_matches: Vec::new(),
+                        detail: entry.command.keys_label(),
+                        badge: None,
+                        indent: 0,
+                    })
+                    .collect(),
+                format!(
+                    "{} commands | ↑↓ navigate | Enter to run | Esc to close",
+                    self.filtered_commands.len()
+                ),
+            ),
+            PaletteMode::Symbols => (
+                self.filtered_headings
+                    .iter()
+                    .skip(self.scroll_top)
+                    .take(MAX_VISIBLE_ITEMS)
+                    .map(|entry| ResultRow {
+                        label: entry.heading.title.clone(),
+                        label_matches: entry.matched_indices.clone(),
+                        context: String::new(),
+                        context_matches: Vec::new(),
+                        detail: format!("line {}", entry.heading.line + 1),
+                        badge: Some((format!("H{}", entry.heading.level), rgb(0x569CD6))),
+                        indent: entry.heading.level - 1,
+                    })
+                    .collect(),
+                format!(
+                    "{} headings | ↑↓ navigate | Enter to jump | Esc to close",
+                    self.filtered_headings.len()
+                ),
+            ),
+            PaletteMode::WorkspaceSymbols => (
+                self.filtered_workspace_headings
+                    .iter()
+                    .skip(self.scroll_top)
+                    .take(MAX_VISIBLE_ITEMS)
+                    .map(|entry| self.workspace_heading_row(entry))
+                    .collect(),
+                format!(
+                    "{} headings in workspace{} | ↑↓ navigate | Enter to open | Esc to close",
+                    self.filtered_workspace_headings.len(),
+                    if self.scanning { " (scanning…)" } else { "" }
+                ),
+            ),
+        };
+        let selected_row = self.selected_index.checked_sub(self.scroll_top);
+        self.render_frame(header, rows, selected_row, footer, cx)
+    }
+}
 
+impl Palette {
+    /// Lays out the overlay: header input, result rows, and a footer hint line.
+    fn render_frame(
+        &self,
+        header: gpui::Div,
+        rows: Vec<ResultRow>,
+        selected_row: Option<usize>,
+        footer: String,
+        cx: &mut Context<Self>,
+    ) -> gpui::Div {
         div()
             .track_focus(&self.focus_handle(cx))
             .on_key_down(cx.listener(|palette, event: &KeyDownEvent, _, cx| {
-                // Handle Enter - mark that user wants to open the selected file
+                palette.error = None;
+
+                // The action menu owns the keyboard while it is open
+                if palette.file_actions.is_some() {
+                    palette.handle_file_action_key(event, cx);
+                    cx.stop_propagation();
+                    return;
+                }
+
+                // Ctrl+Enter opens the action menu for the selected file
+                if event.keystroke.key == "enter" && event.keystroke.modifiers.control {
+                    palette.open_file_actions(cx);
+                    return;
+                }
+
+                // Handle Enter - open the selected file, run the command, or create the file
                 if event.keystroke.key == "enter" {
-                    palette.should_open = true;
-                    cx.notify();
+                    palette.confirm(cx);
                     return;
                 }
 
@@ -256,12 +1019,6 @@ impl Render for Palette {
                     return;
                 }
 
-                // Handle backspace
-                if event.keystroke.key == "backspace" {
-                    palette.handle_backspace(cx);
-                    return;
-                }
-
                 // Handle arrow keys
                 if event.keystroke.key == "up" {
                     palette.handle_up(cx);
@@ -272,19 +1029,9 @@ impl Render for Palette {
                     return;
                 }
 
-                // Handle regular character input
-                if let Some(key_char) = &event.keystroke.key_char {
-                    if key_char.len() == 1
-                        && !event.keystroke.modifiers.control
-                        && !event.keystroke.modifiers.alt
-                        && !event.keystroke.modifiers.platform
-                    {
-                        if let Some(c) = key_char.chars().next() {
-                            if c.is_ascii_graphic() || c == ' ' {
-                                palette.handle_char_input(c, cx);
-                            }
-                        }
-                    }
+                // Everything else edits the query (typing, caret motion, clipboard)
+                if palette.handle_query_key(event, cx) {
+                    cx.stop_propagation();
                 }
             }))
             .absolute()
@@ -303,18 +1050,10 @@ impl Render for Palette {
             // Search input area
             .child(
                 div().p_3().border_b_1().border_color(rgb(0x454545)).child(
-                    div()
+                    header
                         .text_sm()
                         .text_color(rgb(0xcccccc))
-                        .font_family("monospace")
-                        .child(format!(
-                            "> {}",
-                            if self.query.is_empty() {
-                                "Type to search...".to_string()
-                            } else {
-                                self.query.clone()
-                            }
-                        )),
+                        .font_family("monospace"),
                 ),
             )
             // Results list
@@ -324,38 +1063,97 @@ impl Render for Palette {
                     .flex_col()
                     .overflow_hidden()
                     .max_h(px(300.0))
-                    .children(
-                        self.filtered_files
-                            .iter()
-                            .take(max_visible_items)
-                            .enumerate()
-                            .map(|(idx, file)| {
-                                let is_selected = idx == self.selected_index;
+                    .children(rows.into_iter().enumerate().map(|(idx, row)| {
+                        let is_selected = selected_row == Some(idx);
+                        let mut label = div().flex().flex_row().items_center().gap_2();
+                        if let Some((badge, color)) = row.badge {
+                            label = label.child(
                                 div()
-                                    .p_2()
-                                    .pl_3()
-                                    .when(is_selected, |div| div.bg(rgb(0x094771)))
-                                    .when(!is_selected, |div| div.bg(rgb(0x2d2d2d)))
-                                    .child(
-                                        div()
-                                            .text_sm()
-                                            .font_family("monospace")
-                                            .text_color(if is_selected {
-                                                rgb(0xffffff)
-                                            } else {
-                                                rgb(0xd4d4d4)
-                                            })
-                                            .child(file.display_name.clone()),
-                                    )
-                            }),
-                    ),
+                                    .w(px(40.0))
+                                    .px_1()
+                                    .rounded_sm()
+                                    .border_1()
+                                    .border_color(color)
+                                    .text_xs()
+                                    .text_color(color)
+                                    .child(badge),
+                            );
+                        }
+                        div()
+                            .p_2()
+                            .pl(px(12.0 + row.indent as f32 * 16.0))
+                            .flex()
+                            .flex_row()
+                            .justify_between()
+                            .when(is_selected, |div| div.bg(rgb(0x094771)))
+                            .when(!is_selected, |div| div.bg(rgb(0x2d2d2d)))
+                            .child(
+                                label
+                                    .text_sm()
+                                    .font_family("monospace")
+                                    .child(Self::highlighted_text(
+                                        &row.label,
+                                        &row.label_matches,
+                                        if is_selected {
+                                            rgb(0xffffff)
+                                        } else {
+                                            rgb(0xd4d4d4)
+                                        },
+                                    ))
+                                    .when(!row.context.is_empty(), |label| {
+                                        label.child(
+                                            Self::highlighted_text(
+                                                &row.context,
+                                                &row.context_matches,
+                                                rgb(0x808080),
+                                            )
+                                            .text_xs(),
+                                        )
+                                    }),
+                            )
+                            .child(div().text_xs().text_color(rgb(0x808080)).child(row.detail))
+                    })),
             )
+            .when_some(self.error.as_ref(), |frame, error| {
+                frame.child(
+                    div()
+                        .p_2()
+                        .border_t_1()
+                        .border_color(rgb(0x454545))
+                        .text_xs()
+                        .text_color(rgb(0xF48771))
+                        .child(error.clone()),
+                )
+            })
             // Footer with hints
-            .child(div().p_2().border_t_1().border_color(rgb(0x454545)).child(
-                div().text_xs().text_color(rgb(0x808080)).child(format!(
-                    "{} files | ↑↓ navigate | Enter to open | Esc to close",
-                    self.filtered_files.len()
-                )),
+            .child(
+                div()
+                    .p_2()
+                    .border_t_1()
+                    .border_color(rgb(0x454545))
+                    .child(div().text_xs().text_color(rgb(0x808080)).child(footer)),
+            )
+    }
+}
+
+/// Sorts entries best match first; the sort is stable, so equal scores keep
+/// their order.
+fn sort_by_score<T>(entries: &mut [T], score: impl Fn(&T) -> Option<i32>) {
+    entries.sort_by_key(|entry| Reverse(score(entry).unwrap_or(0)));
+}
+
+/// Suggests a name for a duplicate: `notes/todo.md` becomes `notes/todo copy.md`.
+fn copy_name(display_name: &str) -> String {
+    let path = Path::new(display_name);
+    match (path.file_stem(), path.extension()) {
+        (Some(stem), Some(ext)) => path
+            .with_file_name(format!(
+                "{} copy.{}",
+                stem.to_string_lossy(),
+                ext.to_string_lossy()
             ))
+            .to_string_lossy()
+            .to_string(),
+        _ => format!("{} copy", display_name),
     }
 }
@@ -0,0 +1,160 @@
+//! Word, line and paragraph boundaries for selection and caret motion.
+//!
+//! A double-click selects the run of characters of the same class around the
+//! click: a word (letters, digits and underscores), a stretch of whitespace or a
+//! run of punctuation. A triple-click selects the whole document line including
+//! its line break, so dragging afterwards extends by whole lines. Word motion
+//! skips whitespace and stops at class changes, and paragraph motion stops at
+//! the blank lines between blocks of text.
+
+use std::ops::Range;
+
+/// How far a click selects, from its click count.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SelectionUnit {
+    Char,
+    Word,
+    Line,
+}
+
+impl SelectionUnit {
+    /// Unit for the `click_count` of a mouse down event.
+    pub fn from_click_count(click_count: usize) -> Self {
+        match click_count {
+            0 | 1 => Self::Char,
+            2 => Self::Word,
+            _ => Self::Line,
+        }
+    }
+}
+
+/// The range of `unit` around `offset`; empty at `offset` for `SelectionUnit::Char`.
+pub fn unit_range(content: &str, offset: usize, unit: SelectionUnit) -> Range<usize> {
+    match unit {
+        SelectionUnit::Char => offset..offset,
+        SelectionUnit::Word => word_range(content, offset),
+        SelectionUnit::Line => line_range(content, offset),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+    Word,
+    Whitespace,
+    Punctuation,
+}
+
+fn char_class(ch: char) -> CharClass {
+    if ch.is_alphanumeric() || ch == '_' {
+        CharClass::Word
+    } else if ch.is_whitespace() {
+        CharClass::Whitespace
+    } else {
+        CharClass::Punctuation
+    }
+}
+
+/// The run of same-class characters at `offset`, never crossing a line break.
+///
+/// Prefers the character after `offset`, falling back to the one before it at
+/// the end of a line.
+pub fn word_range(content: &str, offset: usize) -> Range<usize> {
+    let line = line_range(content, offset);
+    let line_end = line.start + content[line.clone()].trim_end_matches('\n').len();
+    let text = &content[line.start..line_end];
+    let at = offset - line.start;
+
+    let Some(ch) = text[at..]
+        .chars()
+        .next()
+        .or_else(|| text[..at].chars().next_back())
+    else {
+        return offset..offset;
+    };
+    let class = char_class(ch);
+
+    let start = text[..at]
+        .char_indices()
+        .rev()
+        .take_while(|(_, ch)| char_class(*ch) == class)
+        .last()
+        .map_or(at, |(idx, _)| idx);
+    let end = text[at..]
+        .char_indices()
+        .find(|(_, ch)| char_class(*ch) != class)
+        .map_or(text.len(), |(idx, _)| at + idx);
+
+    line.start + start..line.start + end
+}
+
+/// The document line containing `offset`, including its trailing line break.
+pub fn line_range(content: &str, offset: usize) -> Range<usize> {
+    let start = content[..offset].rfind('\n').map_or(0, |idx| idx + 1);
+    let end = content[offset..]
+        .find('\n')
+        .map_or(content.len(), |idx| offset + idx + 1);
+    start..end
+}
+
+/// Start of the word before `offset`, skipping any whitespace in between.
+pub fn prev_word_start(content: &str, offset: usize) -> usize {
+    let mut chars = content[..offset].char_indices().rev().peekable();
+    while chars.next_if(|(_, ch)| ch.is_whitespace()).is_some() {}
+
+    let Some(&(_, first)) = chars.peek() else {
+        return 0;
+    };
+    let class = char_class(first);
+    let mut start = offset;
+    while let Some((idx, _)) = chars.next_if(|(_, ch)| char_class(*ch) == class) {
+        start = idx;
+    }
+    start
+}
+
+/// End of the word after `offset`, skipping any whitespace in between.
+pub fn next_word_end(content: &str, offset: usize) -> usize {
+    let mut chars = content[offset..].char_indices().peekable();
+    while chars.next_if(|(_, ch)| ch.is_whitespace()).is_some() {}
+
+    let Some(&(_, first)) = chars.peek() else {
+        return content.len();
+    };
+    let class = char_class(first);
+    let mut end = offset;
+    while let Some((idx, ch)) = chars.next_if(|(_, ch)| char_class(*ch) == class) {
+        end = offset + idx + ch.len_utf8();
+    }
+    end
+}
+
+/// Start of the next (`forward`) or previous blank line that ends a paragraph.
+///
+/// From a blank line, the blank lines next to it are skipped first, so repeated
+/// jumps move from gap to gap. Falls back to the end or start of the document.
+pub fn paragraph_boundary(content: &str, offset: usize, forward: bool) -> usize {
+    let line = line_range(content, offset);
+    let mut starts: Vec<usize> = std::iter::once(0)
+        .chain(content.match_indices('\n').map(|(idx, _)| idx + 1))
+        .collect();
+    let is_blank = |start: usize| {
+        let end = content[start..]
+            .find('\n')
+            .map_or(content.len(), |idx| start + idx);
+        content[start..end].trim().is_empty()
+    };
+
+    if forward {
+        starts.retain(|&start| start > line.start);
+    } else {
+        starts.retain(|&start| start < line.start);
+        starts.reverse();
+    }
+    let fallback = if forward { content.len() } else { 0 };
+
+    let mut starts = starts.into_iter().peekable();
+    if is_blank(line.start) {
+        while starts.next_if(|&start| is_blank(start)).is_some() {}
+    }
+    starts.find(|&start| is_blank(start)).unwrap_or(fallback)
+}
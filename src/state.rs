@@ -0,0 +1,71 @@
+//! Persistent application state stored between sessions.
+//!
+//! State lives under `$XDG_STATE_HOME/medleytext` (falling back to
+//! `~/.local/state/medleytext`). Files are small line-based text files so they
+//! stay human-readable and need no serialization dependency.
+
+use std::path::{Path, PathBuf};
+
+/// Returns the directory used for persisted state, if one can be determined.
+pub fn state_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_STATE_HOME")
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("state"))
+        })?;
+    Some(base.join("medleytext"))
+}
+
+/// Reads a state file as a list of lines.
+///
+/// Missing or unreadable files yield an empty list so callers can treat
+/// first-run and corrupted state the same way.
+pub fn read_lines(name: &str) -> Vec<String> {
+    let Some(path) = state_dir().map(|dir| dir.join(name)) else {
+        return Vec::new();
+    };
+
+    std::fs::read_to_string(path)
+        .map(|content| content.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// Writes a list of lines to a state file, creating the state directory on demand.
+///
+/// Errors are logged to stderr; losing state should never interrupt editing.
+pub fn write_lines(name: &str, lines: &[String]) {
+    let Some(dir) = state_dir() else {
+        return;
+    };
+
+    if let Err(e) = std::fs::create_dir_all(&dir) {
+        eprintln!("Failed to create state directory: {}", e);
+        return;
+    }
+
+    let mut content = lines.join("\n");
+    if !content.is_empty() {
+        content.push('\n');
+    }
+
+    if let Err(e) = std::fs::write(dir.join(name), content) {
+        eprintln!("Failed to write state file {}: {}", name, e);
+    }
+}
+
+/// State file name for data kept per path, e.g. `open_history_<hash>`.
+pub fn keyed_name(prefix: &str, path: &Path) -> String {
+    format!(
+        "{}_{:016x}",
+        prefix,
+        fnv1a(path.to_string_lossy().as_bytes())
+    )
+}
+
+/// FNV-1a hash; stable across builds, unlike `DefaultHasher`.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
@@ -0,0 +1,410 @@
+//! Single-line text input used by overlays such as the find panel and palette.
+//!
+//! The input owns its text, caret, and selection but no focus handle; the host
+//! view forwards key events and decides when the input is active. This keeps the
+//! component a plain struct like the other overlay state in the editor.
+
+use gpui::{App, ClipboardItem, Div, KeyDownEvent, Keystroke, div, prelude::*, px, rgb};
+
+/// Editing operations understood by [`TextInput`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InputCommand {
+    MoveLeft,
+    MoveRight,
+    SelectLeft,
+    SelectRight,
+    MoveWordLeft,
+    MoveWordRight,
+    SelectWordLeft,
+    SelectWordRight,
+    MoveHome,
+    MoveEnd,
+    SelectHome,
+    SelectEnd,
+    SelectAll,
+    Backspace,
+    Delete,
+    DeleteWordBackward,
+    DeleteWordForward,
+    Copy,
+    Cut,
+    Paste,
+    Insert(String),
+}
+
+/// Result of applying a command, so hosts know whether to refilter or just repaint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputResponse {
+    /// The key was not meant for the input.
+    Ignored,
+    /// Caret or selection moved, text unchanged.
+    Moved,
+    /// Text content changed.
+    Edited,
+}
+
+impl InputResponse {
+    /// Returns true when the key was consumed.
+    pub fn handled(self) -> bool {
+        self != InputResponse::Ignored
+    }
+}
+
+/// Editable single-line text field with caret, selection, and clipboard support.
+///
+/// Positions are byte offsets into `text`, always kept on char boundaries.
+#[derive(Clone, Debug, Default)]
+pub struct TextInput {
+    text: String,
+    cursor: usize,
+    anchor: Option<usize>,
+}
+
+impl TextInput {
+    /// Creates an input with the caret placed after `text`.
+    pub fn new(text: impl Into<String>) -> Self {
+        let mut input = Self::default();
+        input.set_text(text);
+        input
+    }
+
+    /// Current text content.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns true when the input holds no text.
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Replaces the content, moving the caret to the end and clearing the selection.
+    pub fn set_text(&mut self, text: impl Into<String>) {
+        self.text = Self::sanitize(&text.into());
+        self.cursor = self.text.len();
+        self.anchor = None;
+    }
+
+    /// Normalized selection as (start, end) byte offsets, if any.
+    pub fn selection_range(&self) -> Option<(usize, usize)> {
+        self.anchor
+            .filter(|&anchor| anchor != self.cursor)
+            .map(|anchor| (anchor.min(self.cursor), anchor.max(self.cursor)))
+    }
+
+    /// Maps a keystroke to an editing command using common text field conventions.
+    pub fn command_for_keystroke(keystroke: &Keystroke) -> Option<InputCommand> {
+        let modifiers = &keystroke.modifiers;
+        if modifiers.platform {
+            return None;
+        }
+        let word = modifiers.control || modifiers.alt;
+        let shift = modifiers.shift;
+
+        let command = match keystroke.key.as_str() {
+            "left" if word && shift => InputCommand::SelectWordLeft,
+            "left" if word => InputCommand::MoveWordLeft,
+            "left" if shift => InputCommand::SelectLeft,
+            "left" => InputCommand::MoveLeft,
+            "right" if word && shift => InputCommand::SelectWordRight,
+            "right" if word => InputCommand::MoveWordRight,
+            "right" if shift => InputCommand::SelectRight,
+            "right" => InputCommand::MoveRight,
+            "home" if shift => InputCommand::SelectHome,
+            "home" => InputCommand::MoveHome,
+            "end" if shift => InputCommand::SelectEnd,
+            "end" => InputCommand::MoveEnd,
+            "backspace" if word => InputCommand::DeleteWordBackward,
+            "backspace" => InputCommand::Backspace,
+            "delete" if word => InputCommand::DeleteWordForward,
+            "delete" => InputCommand::Delete,
+            "a" if modifiers.control && !modifiers.alt => InputCommand::SelectAll,
+            "c" if modifiers.control && !modifiers.alt => InputCommand::Copy,
+            "x" if modifiers.control && !modifiers.alt => InputCommand::Cut,
+            "v" if modifiers.control && !modifiers.alt => InputCommand::Paste,
+            "w" if modifiers.control && !modifiers.alt => InputCommand::DeleteWordBackward,
+            _ => {
+                if modifiers.control || modifiers.alt {
+                    return None;
+                }
+                let key_char = keystroke.key_char.as_ref()?;
+                if key_char.is_empty() || key_char.chars().any(char::is_control) {
+                    return None;
+                }
+                InputCommand::Insert(key_char.clone())
+            }
+        };
+
+        Some(command)
+    }
+
+    /// Applies the command mapped from a key event, if any.
+    pub fn handle_key_down(&mut self, event: &KeyDownEvent, cx: &mut App) -> InputResponse {
+        match Self::command_for_keystroke(&event.keystroke) {
+            Some(command) => self.apply(command, cx),
+            None => InputResponse::Ignored,
+        }
+    }
+
+    /// Applies an editing command.
+    pub fn apply(&mut self, command: InputCommand, cx: &mut App) -> InputResponse {
+        match command {
+            InputCommand::MoveLeft => {
+                let target = match self.selection_range() {
+                    Some((start, _)) => start,
+                    None => self.prev_boundary(self.cursor),
+                };
+                self.move_to(target, false)
+            }
+            InputCommand::MoveRight => {
+                let target = match self.selection_range() {
+                    Some((_, end)) => end,
+                    None => self.next_boundary(self.cursor),
+                };
+                self.move_to(target, false)
+            }
+            InputCommand::SelectLeft => self.move_to(self.prev_boundary(self.cursor), true),
+            InputCommand::SelectRight => self.move_to(self.next_boundary(self.cursor), true),
+            InputCommand::MoveWordLeft => self.move_to(self.prev_word_start(self.cursor), false),
+            InputCommand::MoveWordRight => self.move_to(self.next_word_end(self.cursor), false),
+            InputCommand::SelectWordLeft => self.move_to(self.prev_word_start(self.cursor), true),
+            InputCommand::SelectWordRight => self.move_to(self.next_word_end(self.cursor), true),
+            InputCommand::MoveHome => self.move_to(0, false),
+            InputCommand::MoveEnd => self.move_to(self.text.len(), false),
+            InputCommand::SelectHome => self.move_to(0, true),
+            InputCommand::SelectEnd => self.move_to(self.text.len(), true),
+            InputCommand::SelectAll => {
+                self.anchor = Some(0);
+                self.cursor = self.text.len();
+                InputResponse::Moved
+            }
+            InputCommand::Backspace => {
+                if self.delete_selection() {
+                    return InputResponse::Edited;
+                }
+                let start = self.prev_boundary(self.cursor);
+                self.delete_range(start, self.cursor)
+            }
+            InputCommand::Delete => {
+                if self.delete_selection() {
+                    return InputResponse::Edited;
+                }
+                let end = self.next_boundary(self.cursor);
+                self.delete_range(self.cursor, end)
+            }
+            InputCommand::DeleteWordBackward => {
+                if self.delete_selection() {
+                    return InputResponse::Edited;
+                }
+                let start = self.prev_word_start(self.cursor);
+                self.delete_range(start, self.cursor)
+            }
+            InputCommand::DeleteWordForward => {
+                if self.delete_selection() {
+                    return InputResponse::Edited;
+                }
+                let end = self.next_word_end(self.cursor);
+                self.delete_range(self.cursor, end)
+            }
+            InputCommand::Copy => {
+                if let Some((start, end)) = self.selection_range() {
+                    cx.write_to_clipboard(ClipboardItem::new_string(
+                        self.text[start..end].to_string(),
+                    ));
+                }
+                InputResponse::Moved
+            }
+            InputCommand::Cut => {
+                if let Some((start, end)) = self.selection_range() {
+                    cx.write_to_clipboard(ClipboardItem::new_string(
+                        self.text[start..end].to_string(),
+                    ));
+                    self.delete_selection();
+                    InputResponse::Edited
+                } else {
+                    InputResponse::Moved
+                }
+            }
+            InputCommand::Paste => {
+                let text = cx
+                    .read_from_clipboard()
+                    .and_then(|item| item.text().map(|s| s.to_string()));
+                match text {
+                    Some(text) => self.insert(&text),
+                    None => InputResponse::Moved,
+                }
+            }
+            InputCommand::Insert(text) => self.insert(&text),
+        }
+    }
+
+    /// Inserts text at the caret, replacing any selection.
+    ///
+    /// Line breaks are collapsed to spaces since the input is single-line.
+    pub fn insert(&mut self, text: &str) -> InputResponse {
+        let text = Self::sanitize(text);
+        let had_selection = self.delete_selection();
+        if text.is_empty() {
+            return if had_selection {
+                InputResponse::Edited
+            } else {
+                InputResponse::Moved
+            };
+        }
+        self.text.insert_str(self.cursor, &text);
+        self.cursor += text.len();
+        InputResponse::Edited
+    }
+
+    /// Renders the text with caret and selection highlighting.
+    ///
+    /// The caret is only drawn when `focused` is true. An empty input shows
+    /// `placeholder` in a dimmed color instead.
+    pub fn render(&self, placeholder: &str, focused: bool) -> Div {
+        let caret = || div().w(px(2.0)).h(px(16.0)).bg(rgb(0xcccccc));
+        let mut row = div().flex().flex_row().items_center().min_h(px(16.0));
+
+        if self.text.is_empty() {
+            if focused {
+                row = row.child(caret());
+            }
+            return row.child(
+                div()
+                    .text_color(rgb(0x707070))
+                    .child(placeholder.to_string()),
+            );
+        }
+
+        let (sel_start, sel_end) = self.selection_range().unwrap_or((self.cursor, self.cursor));
+        let mut boundaries = vec![0, sel_start, sel_end, self.cursor, self.text.len()];
+        boundaries.sort_unstable();
+        boundaries.dedup();
+
+        for window in boundaries.windows(2) {
+            let (start, end) = (window[0], window[1]);
+            if focused && start == self.cursor {
+                row = row.child(caret());
+            }
+            let selected = start >= sel_start && end <= sel_end && sel_start != sel_end;
+            let mut piece = div()
+                .text_color(rgb(0xffffff))
+                .child(self.text[start..end].to_string());
+            if selected {
+                piece = piece.bg(rgb(0x264F78));
+            }
+            row = row.child(piece);
+        }
+
+        if focused && self.cursor == self.text.len() {
+            row = row.child(caret());
+        }
+
+        row
+    }
+
+    fn sanitize(text: &str) -> String {
+        text.replace(['\r', '\n'], " ")
+    }
+
+    fn move_to(&mut self, target: usize, select: bool) -> InputResponse {
+        if select {
+            if self.anchor.is_none() {
+                self.anchor = Some(self.cursor);
+            }
+        } else {
+            self.anchor = None;
+        }
+        self.cursor = target;
+        InputResponse::Moved
+    }
+
+    fn delete_selection(&mut self) -> bool {
+        match self.selection_range() {
+            Some((start, end)) => {
+                self.text.drain(start..end);
+                self.cursor = start;
+                self.anchor = None;
+                true
+            }
+            None => {
+                self.anchor = None;
+                false
+            }
+        }
+    }
+
+    fn delete_range(&mut self, start: usize, end: usize) -> InputResponse {
+        if start == end {
+            return InputResponse::Moved;
+        }
+        self.text.drain(start..end);
+        self.cursor = start;
+        InputResponse::Edited
+    }
+
+    fn prev_boundary(&self, offset: usize) -> usize {
+        self.text[..offset]
+            .char_indices()
+            .next_back()
+            .map(|(idx, _)| idx)
+            .unwrap_or(0)
+    }
+
+    fn next_boundary(&self, offset: usize) -> usize {
+        self.text[offset..]
+            .chars()
+            .next()
+            .map(|c| offset + c.len_utf8())
+            .unwrap_or(offset)
+    }
+
+    /// Start of the word before `offset`, skipping any whitespace in between.
+    fn prev_word_start(&self, offset: usize) -> usize {
+        let mut chars = self.text[..offset].char_indices().rev().peekable();
+        while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+
+        let Some(&(_, first)) = chars.peek() else {
+            return 0;
+        };
+        let class = char_class(first);
+        let mut start = offset;
+        while let Some((idx, _)) = chars.next_if(|(_, c)| char_class(*c) == class) {
+            start = idx;
+        }
+        start.min(offset)
+    }
+
+    /// End of the word after `offset`, skipping any whitespace in between.
+    fn next_word_end(&self, offset: usize) -> usize {
+        let mut chars = self.text[offset..].char_indices().peekable();
+        while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+
+        let Some(&(_, first)) = chars.peek() else {
+            return self.text.len();
+        };
+        let class = char_class(first);
+        let mut end = offset;
+        while let Some((idx, c)) = chars.next_if(|(_, c)| char_class(*c) == class) {
+            end = offset + idx + c.len_utf8();
+        }
+        end
+    }
+}
+
+/// Coarse character classes used for word motion.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+    Whitespace,
+    Word,
+    Punctuation,
+}
+
+fn char_class(c: char) -> CharClass {
+    if c.is_whitespace() {
+        CharClass::Whitespace
+    } else if c.is_alphanumeric() || c == '_' {
+        CharClass::Word
+    } else {
+        CharClass::Punctuation
+    }
+}
@@ -0,0 +1,83 @@
+//! Measurement of the document text with GPUI's text system.
+//!
+//! Row height is derived from the font size and set explicitly on every row, and
+//! horizontal positions come from shaping the row's text, so hit-testing agrees
+//! with what is drawn for any glyph, font or display scale.
+
+use gpui::{Font, Pixels, Rems, ShapedLine, TextRun, Window, black, font, px};
+
+/// Font family of the document text.
+pub const EDITOR_FONT_FAMILY: &str = "monospace";
+
+/// Font size of the document text.
+pub const EDITOR_FONT_SIZE: Rems = Rems(0.875);
+
+/// Row height relative to the font size.
+const LINE_HEIGHT_SCALE: f32 = 1.6;
+
+/// Font metrics of the document text at the window's current scale.
+#[derive(Clone, Debug)]
+pub struct TextMetrics {
+    font: Font,
+    pub font_size: Pixels,
+    /// Height of one screen row
+    pub line_height: Pixels,
+    /// Advance of one column, used for wrap widths and hanging indents
+    pub char_width: Pixels,
+}
+
+impl Default for TextMetrics {
+    /// Estimates used until the first render measures the real font.
+    fn default() -> Self {
+        Self {
+            font: font(EDITOR_FONT_FAMILY),
+            font_size: px(14.0),
+            line_height: px(22.0),
+            char_width: px(8.4),
+        }
+    }
+}
+
+impl TextMetrics {
+    /// Measures the document font as it will be drawn in `window`.
+    pub fn measure(window: &Window) -> Self {
+        let font = font(EDITOR_FONT_FAMILY);
+        let font_size = EDITOR_FONT_SIZE.to_pixels(window.rem_size());
+        let text_system = window.text_system();
+        let font_id = text_system.resolve_font(&font);
+        let char_width = text_system
+            .em_advance(font_id, font_size)
+            .unwrap_or(font_size * 0.6);
+
+        Self {
+            font,
+            font_size,
+            line_height: (font_size * LINE_HEIGHT_SCALE).round(),
+            char_width,
+        }
+    }
+
+    /// Byte index in `text` of the caret position closest to `x`.
+    pub fn index_for_x(&self, text: &str, x: Pixels, window: &Window) -> usize {
+        self.shape(text, window).closest_index_for_x(x)
+    }
+
+    /// Horizontal position of the caret before byte `index` of `text`.
+    pub fn x_for_index(&self, text: &str, index: usize, window: &Window) -> Pixels {
+        self.shape(text, window).x_for_index(index)
+    }
+
+    fn shape(&self, text: &str, window: &Window) -> ShapedLine {
+        let run = TextRun {
+            len: text.len(),
+            font: self.font.clone(),
+            color: black(),
+            background_color: None,
+            underline: None,
+            strikethrough: None,
+        };
+        window
+            .text_system()
+            .shape_line(text.to_string().into(), self.font_size, &[run], None)
+    }
+}
@@ -0,0 +1,572 @@
+//! Background index of the documents in the working directory.
+//!
+//! The index walks the workspace once on a background thread, streaming
+//! partial results to observers while the walk is in progress. Every directory
+//! it lists gets a native, non-recursive watch (through `notify`), so afterwards
+//! the index only touches the paths that were reported: a new directory is
+//! scanned, a changed document is indexed again and a removed path drops its
+//! entries. A changed ignore or config file rescans the whole workspace, since
+//! it can change which files are indexed anywhere. A single index is owned by
+//! the editor and shared with every view that needs the file list.
+//!
+//! If no watcher can be started, the index keeps the result of the initial
+//! scan.
+//!
+//! Headings of markdown files are extracted during the scan with the same
+//! parser the highlighter uses, so workspace-wide heading search never has to
+//! read files while the user types. A document that changes on disk has its
+//! headings extracted again, whether the editor or another program wrote it;
+//! saving refreshes them right away instead of with the next event batch.
+//!
+//! Every directory that is not ignored is recorded as well, so the file tree
+//! can show folders that do not contain any documents yet.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use gpui::{Context, Task};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::config::{Config, FinderConfig, PROJECT_CONFIG_FILE};
+use crate::ignore::{IGNORE_FILE_NAMES, IgnoreStack};
+use crate::markdown::{Heading, MarkdownHighlighter};
+
+/// How often partial scan results are flushed to observers.
+const STREAM_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How often file system events are collected and applied as one batch.
+const EVENT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Bytes read from extension-less files to decide whether they are text.
+const SNIFF_BYTES: usize = 1024;
+
+/// Extensions treated as markdown.
+const MARKDOWN_EXTENSIONS: [&str; 3] = ["md", "markdown", "mdx"];
+
+/// How a document was recognized.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FileKind {
+    /// One of the configured extensions, stored lowercase without the dot.
+    Extension(String),
+    /// No extension, but the content looks like text.
+    PlainText,
+}
+
+impl FileKind {
+    /// Returns true for markdown documents.
+    pub fn is_markdown(&self) -> bool {
+        matches!(self, FileKind::Extension(ext) if MARKDOWN_EXTENSIONS.contains(&ext.as_str()))
+    }
+
+    /// Short uppercase label shown next to the file name, e.g. `MD` or `TXT`.
+    pub fn badge(&self) -> String {
+        match self {
+            FileKind::Extension(ext) => ext.to_uppercase(),
+            FileKind::PlainText => "TXT".to_string(),
+        }
+    }
+}
+
+/// A document discovered in the workspace.
+#[derive(Clone, Debug)]
+pub struct WorkspaceFile {
+    /// Absolute path to the file
+    pub path: PathBuf,
+    /// Path relative to the workspace root, for display
+    pub display_name: String,
+    /// Document type derived from the extension or content
+    pub kind: FileKind,
+    /// Headings of markdown documents; empty for other kinds
+    pub headings: Arc<[Heading]>,
+}
+
+/// Progress shared between a background walk and the foreground task.
+#[derive(Default)]
+struct ScanProgress {
+    /// Files found since the last flush
+    pending: Vec<WorkspaceFile>,
+    /// Directories found since the last flush
+    pending_dirs: Vec<PathBuf>,
+    done: bool,
+}
+
+/// Native watches on the scanned directories.
+///
+/// Each directory is watched non-recursively, so it reports changes to its
+/// direct entries only; subdirectories get watches of their own as they are
+/// scanned.
+struct DirWatcher {
+    /// `None` when no native watcher could be started
+    watcher: Option<RecommendedWatcher>,
+    /// Events reported since the last batch
+    events: Receiver<notify::Result<Event>>,
+    /// Directories currently watched
+    watched: HashSet<PathBuf>,
+}
+
+/// Paths reported by the watcher in one batch.
+enum Changes {
+    /// Rule files changed or events were lost; everything must be rescanned.
+    Full,
+    /// Only these paths changed.
+    Paths(Vec<PathBuf>),
+}
+
+/// How the index changes after a batch of events, computed in the background.
+struct IndexUpdate {
+    /// Files and directories at or below these paths are dropped first
+    removed: Vec<PathBuf>,
+    /// Documents found at the changed paths
+    files: Vec<WorkspaceFile>,
+    /// Directories found at the changed paths
+    dirs: Vec<PathBuf>,
+}
+
+impl DirWatcher {
+    fn new() -> Self {
+        let (sender, events) = mpsc::channel();
+        let watcher = notify::recommended_watcher(sender)
+            .inspect_err(|e| eprintln!("Failed to watch the workspace: {}", e))
+            .ok();
+        Self {
+            watcher,
+            events,
+            watched: HashSet::new(),
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        self.watcher.is_some()
+    }
+
+    fn watch(&mut self, dir: &Path) {
+        let Some(watcher) = &mut self.watcher else {
+            return;
+        };
+        if !self.watched.contains(dir) && watcher.watch(dir, RecursiveMode::NonRecursive).is_ok() {
+            self.watched.insert(dir.to_path_buf());
+        }
+    }
+
+    /// Drops the watches on `path` and every directory below it.
+    fn unwatch_under(&mut self, path: &Path) {
+        let Some(watcher) = &mut self.watcher else {
+            return;
+        };
+        self.watched.retain(|dir| {
+            if !dir.starts_with(path) {
+                return true;
+            }
+            // A deleted directory has already lost its watch
+            let _ = watcher.unwatch(dir);
+            false
+        });
+    }
+
+    /// Collects the paths reported since the last call, or `None` if nothing changed.
+    fn take_changes(&self, root: &Path) -> Option<Changes> {
+        let mut paths = Vec::new();
+        for event in self.events.try_iter() {
+            match event {
+                // Reading documents for their headings is reported as access
+                Ok(event) if matches!(event.kind, EventKind::Access(_)) => {}
+                Ok(event) if !event.need_rescan() => paths.extend(event.paths),
+                _ => return Some(Changes::Full),
+            }
+        }
+
+        let changes_rules = paths.iter().any(|path| {
+            *path == root.join(PROJECT_CONFIG_FILE)
+                || path
+                    .file_name()
+                    .is_some_and(|name| IGNORE_FILE_NAMES.iter().any(|rule| name == *rule))
+        });
+        if changes_rules {
+            return Some(Changes::Full);
+        }
+
+        paths.sort();
+        paths.dedup();
+        (!paths.is_empty()).then_some(Changes::Paths(paths))
+    }
+}
+
+/// Shared, continuously updated list of workspace documents.
+pub struct WorkspaceIndex {
+    /// Canonical workspace root
+    root: PathBuf,
+    /// Known files, sorted by display name once a scan completes
+    files: Vec<WorkspaceFile>,
+    /// Scanned directories, including the root, sorted once a scan completes
+    dirs: Vec<PathBuf>,
+    /// True while the initial scan is still streaming results
+    scanning: bool,
+    /// Background scan and watch loop; dropped with the index
+    _task: Task<()>,
+}
+
+impl WorkspaceIndex {
+    /// Creates the index and starts scanning `root` in the background.
+    pub fn new(root: PathBuf, cx: &mut Context<Self>) -> Self {
+        // Canonical root keeps every indexed path absolute and comparable
+        let root = root.canonicalize().unwrap_or(root);
+        let task = Self::spawn_scan_loop(root.clone(), cx);
+        Self {
+            root,
+            files: Vec::new(),
+            dirs: Vec::new(),
+            scanning: true,
+            _task: task,
+        }
+    }
+
+    /// Canonical path of the workspace root.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Files discovered so far.
+    pub fn files(&self) -> &[WorkspaceFile] {
+        &self.files
+    }
+
+    /// Directories discovered so far, including the root.
+    pub fn directories(&self) -> &[PathBuf] {
+        &self.dirs
+    }
+
+    /// Re-reads the headings of one file, e.g. after the editor saved it.
+    pub fn refresh_headings(&mut self, path: &Path, cx: &mut Context<Self>) {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let Some(file) = self.files.iter_mut().find(|file| file.path == path) else {
+            return;
+        };
+        file.headings = extract_headings(&file.path, &file.kind);
+        cx.notify();
+    }
+
+    /// Returns true while the initial scan has not finished.
+    pub fn is_scanning(&self) -> bool {
+        self.scanning
+    }
+
+    /// Runs the initial streaming scan, then applies file system events forever.
+    fn spawn_scan_loop(root: PathBuf, cx: &mut Context<Self>) -> Task<()> {
+        cx.spawn(async move |this, cx| {
+            // Initial scan: stream batches into the index as they are found.
+            let progress = Arc::new(Mutex::new(ScanProgress::default()));
+            let scan = cx.background_executor().spawn({
+                let root = root.clone();
+                let progress = progress.clone();
+                async move {
+                    let mut watcher = DirWatcher::new();
+                    scan_subtree(&root, &root, &progress, &mut watcher);
+                    progress.lock().unwrap().done = true;
+                    watcher
+                }
+            });
+
+            loop {
+                cx.background_executor().timer(STREAM_INTERVAL).await;
+                let (batch, dirs, done) = {
+                    let mut progress = progress.lock().unwrap();
+                    (
+                        std::mem::take(&mut progress.pending),
+                        std::mem::take(&mut progress.pending_dirs),
+                        progress.done,
+                    )
+                };
+                let applied = this.update(cx, |index, cx| {
+                    index.files.extend(batch);
+                    index.dirs.extend(dirs);
+                    if done {
+                        index.scanning = false;
+                        index.sort_files();
+                    }
+                    cx.notify();
+                });
+                if applied.is_err() {
+                    return;
+                }
+                if done {
+                    break;
+                }
+            }
+
+            let mut watcher = scan.await;
+            if !watcher.is_active() {
+                return;
+            }
+
+            // Watch loop: update only the paths the watcher reported.
+            loop {
+                cx.background_executor().timer(EVENT_INTERVAL).await;
+                let Some(changes) = watcher.take_changes(&root) else {
+                    continue;
+                };
+
+                let update;
+                (update, watcher) = cx
+                    .background_executor()
+                    .spawn({
+                        let root = root.clone();
+                        async move {
+                            let update = index_update(&root, changes, &mut watcher);
+                            (update, watcher)
+                        }
+                    })
+                    .await;
+
+                let applied = this.update(cx, |index, cx| {
+                    index.apply_update(update);
+                    cx.notify();
+                });
+                if applied.is_err() {
+                    return;
+                }
+            }
+        })
+    }
+
+    /// Replaces the entries at the changed paths with what was found there.
+    fn apply_update(&mut self, update: IndexUpdate) {
+        let removed = |path: &Path| update.removed.iter().any(|gone| path.starts_with(gone));
+        self.files.retain(|file| !removed(&file.path));
+        self.dirs.retain(|dir| !removed(dir));
+        self.files.extend(update.files);
+        self.dirs.extend(update.dirs);
+        self.sort_files();
+    }
+
+    fn sort_files(&mut self) {
+        self.files
+            .sort_by(|a, b| a.display_name.cmp(&b.display_name));
+        self.files.dedup_by(|a, b| a.path == b.path);
+        self.dirs.sort();
+        self.dirs.dedup();
+    }
+}
+
+/// Walks `dir` (inside `root`) recursively, reporting results through
+/// `progress` and watching every directory it lists.
+///
+/// Ancestor ignore files between `root` and `dir` are loaded first so subtree
+/// scans see the same rules as a full scan; an ignored `dir` is skipped.
+fn scan_subtree(root: &Path, dir: &Path, progress: &Mutex<ScanProgress>, watcher: &mut DirWatcher) {
+    let config = Config::load(root);
+    let mut ignore = match dir.parent() {
+        Some(parent) if dir != root => {
+            let ignore = ignore_stack_for(root, parent, &config.finder);
+            if ignore.is_ignored(dir, true) {
+                return;
+            }
+            ignore
+        }
+        _ => IgnoreStack::new(root, &config.finder),
+    };
+    scan_dir(root, dir, &config.finder, &mut ignore, progress, watcher);
+}
+
+/// Recursive step of `scan_subtree` for a single directory.
+fn scan_dir(
+    root: &Path,
+    dir: &Path,
+    finder: &FinderConfig,
+    ignore: &mut IgnoreStack,
+    progress: &Mutex<ScanProgress>,
+    watcher: &mut DirWatcher,
+) {
+    // Watch before listing so entries created meanwhile are still reported
+    watcher.watch(dir);
+    let pushed = ignore.enter_dir(dir);
+
+    let mut found = Vec::new();
+    let mut subdirs = Vec::new();
+
+    if let Ok(entries) = std::fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            // Symlinked directories are not followed to avoid cycles
+            let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+
+            if ignore.is_ignored(&path, is_dir) {
+                continue;
+            }
+
+            if is_dir {
+                subdirs.push(path);
+            } else if let Some(file) = index_file(root, path, finder, ignore) {
+                found.push(file);
+            }
+        }
+    }
+
+    {
+        let mut progress = progress.lock().unwrap();
+        progress.pending.extend(found);
+        progress.pending_dirs.push(dir.to_path_buf());
+    }
+
+    for subdir in subdirs {
+        scan_dir(root, &subdir, finder, ignore, progress, watcher);
+    }
+
+    ignore.leave_dir(pushed);
+}
+
+/// Indexes a file that passed the ignore rules, if it is a document.
+fn index_file(
+    root: &Path,
+    path: PathBuf,
+    finder: &FinderConfig,
+    ignore: &IgnoreStack,
+) -> Option<WorkspaceFile> {
+    if !ignore.is_included(&path) {
+        return None;
+    }
+    let kind = classify(&path, finder)?;
+    let display_name = path
+        .strip_prefix(root)
+        .unwrap_or(&path)
+        .to_string_lossy()
+        .to_string();
+    let headings = extract_headings(&path, &kind);
+    Some(WorkspaceFile {
+        path,
+        display_name,
+        kind,
+        headings,
+    })
+}
+
+/// Loads the ignore files from `root` down to `dir`, giving the rules for
+/// the entries of `dir`.
+fn ignore_stack_for(root: &Path, dir: &Path, finder: &FinderConfig) -> IgnoreStack {
+    let mut ignore = IgnoreStack::new(root, finder);
+    let mut ancestor = root.to_path_buf();
+    ignore.enter_dir(&ancestor);
+    if let Ok(relative) = dir.strip_prefix(root) {
+        for component in relative.components() {
+            ancestor.push(component);
+            ignore.enter_dir(&ancestor);
+        }
+    }
+    ignore
+}
+
+/// Works out what is now at each reported path, keeping the watches in step.
+///
+/// A path that is gone drops its entries; a new directory is scanned with
+/// everything below it; a file is indexed again. Directories that are already
+/// watched report their own entries, so an event on one needs no rescan.
+fn index_update(root: &Path, changes: Changes, watcher: &mut DirWatcher) -> IndexUpdate {
+    let progress = Mutex::new(ScanProgress::default());
+    let paths = match changes {
+        Changes::Full => {
+            watcher.unwatch_under(root);
+            scan_subtree(root, root, &progress, watcher);
+            let progress = progress.into_inner().unwrap();
+            return IndexUpdate {
+                removed: vec![root.to_path_buf()],
+                files: progress.pending,
+                dirs: progress.pending_dirs,
+            };
+        }
+        Changes::Paths(paths) => paths,
+    };
+
+    let (present, gone): (Vec<_>, Vec<_>) = paths
+        .into_iter()
+        .filter(|path| path.starts_with(root) && path != root)
+        .partition(|path| path.symlink_metadata().is_ok());
+
+    // A renamed directory keeps its watch under the old name; dropping it
+    // first lets the new name get a watch of its own
+    for path in &gone {
+        watcher.unwatch_under(path);
+    }
+
+    let config = Config::load(root);
+    let mut removed = gone;
+    let mut files = Vec::new();
+    for path in present {
+        if path.is_dir() && !path.is_symlink() {
+            if watcher.watched.contains(&path) {
+                continue;
+            }
+            scan_subtree(root, &path, &progress, watcher);
+        } else {
+            let parent = path.parent().unwrap_or(root);
+            let ignore = ignore_stack_for(root, parent, &config.finder);
+            if !ignore.is_ignored(&path, false)
+                && let Some(file) = index_file(root, path.clone(), &config.finder, &ignore)
+            {
+                files.push(file);
+            }
+        }
+        removed.push(path);
+    }
+
+    let progress = progress.into_inner().unwrap();
+    files.extend(progress.pending);
+    IndexUpdate {
+        removed,
+        files,
+        dirs: progress.pending_dirs,
+    }
+}
+
+/// Decides whether a file is indexed and what kind of document it is.
+fn classify(path: &Path, finder: &FinderConfig) -> Option<FileKind> {
+    match path.extension() {
+        Some(ext) => {
+            let ext = ext.to_string_lossy().to_lowercase();
+            finder
+                .extensions
+                .contains(&ext)
+                .then_some(FileKind::Extension(ext))
+        }
+        None => {
+            (finder.sniff_extensionless && looks_like_text(path)).then_some(FileKind::PlainText)
+        }
+    }
+}
+
+/// Reads the headings of a markdown document; other kinds have none.
+fn extract_headings(path: &Path, kind: &FileKind) -> Arc<[Heading]> {
+    if !kind.is_markdown() {
+        return Arc::from([]);
+    }
+    std::fs::read_to_string(path)
+        .map(|content| MarkdownHighlighter::headings(&content).into())
+        .unwrap_or_else(|_| Arc::from([]))
+}
+
+/// Sniffs the start of a file: text has no NUL bytes and decodes as UTF-8.
+fn looks_like_text(path: &Path) -> bool {
+    use std::io::Read;
+
+    let Ok(file) = std::fs::File::open(path) else {
+        return false;
+    };
+    let mut sample = Vec::with_capacity(SNIFF_BYTES);
+    if file
+        .take(SNIFF_BYTES as u64)
+        .read_to_end(&mut sample)
+        .is_err()
+    {
+        return false;
+    }
+
+    if sample.contains(&0) {
+        return false;
+    }
+    match std::str::from_utf8(&sample) {
+        Ok(_) => true,
+        // A multi-byte character cut off by the sample limit is still text
+        Err(e) => e.error_len().is_none(),
+    }
+}
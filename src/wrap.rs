@@ -0,0 +1,165 @@
+//! Soft wrapping of document lines into screen rows.
+//!
+//! Lines longer than the wrap width are broken after whitespace, or mid-word
+//! when a single word does not fit. Continuation rows of list items and
+//! blockquotes are indented to line up with the text after the marker, and
+//! continuation rows of indented lines keep the indentation. Widths are
+//! counted in columns of the monospace font: wide East Asian characters and
+//! emoji take two, combining marks and joiners none, everything else one.
+
+use std::ops::Range;
+
+use crate::markdown::MarkdownHighlighter;
+
+/// Narrowest wrap width honored, in columns; narrower windows overflow instead.
+pub const MIN_WRAP_COLUMNS: usize = 20;
+
+/// One screen row: a slice of a document line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VisualRow {
+    /// Zero-based document line
+    pub line: usize,
+    /// Byte offset of the first character in the document
+    pub start: usize,
+    /// Byte offset after the last character in the document
+    pub end: usize,
+    /// Columns of hanging indentation drawn before the text
+    pub indent: usize,
+    /// True for the last row of its line
+    pub last: bool,
+}
+
+/// Splits `line` into row ranges of at most `width` columns.
+///
+/// Returns byte ranges within `line` with the hanging indent of each row.
+pub fn wrap_line(line: &str, width: usize) -> Vec<(Range<usize>, usize)> {
+    let width = width.max(MIN_WRAP_COLUMNS);
+    let hanging = hanging_indent(line);
+    // A deep indent would leave no room for text
+    let hanging = if hanging * 2 > width { 0 } else { hanging };
+
+    let mut rows = Vec::new();
+    let mut row_start = 0;
+    let mut column = 0;
+    let mut break_at = None;
+
+    for (idx, ch) in line.char_indices() {
+        let available = if rows.is_empty() {
+            width
+        } else {
+            width - hanging
+        };
+        let ch_width = char_width(ch);
+        // Whitespace may hang past the edge; the row breaks after it
+        if column + ch_width > available && !ch.is_whitespace() {
+            let end = match break_at {
+                Some(end) if end > row_start => end,
+                _ => idx,
+            };
+            let indent = if rows.is_empty() { 0 } else { hanging };
+            rows.push((row_start..end, indent));
+            row_start = end;
+            column = str_width(&line[row_start..idx]);
+            break_at = None;
+        }
+        column += ch_width;
+        if ch.is_whitespace() {
+            break_at = Some(idx + ch.len_utf8());
+        }
+    }
+
+    let indent = if rows.is_empty() { 0 } else { hanging };
+    rows.push((row_start..line.len(), indent));
+    rows
+}
+
+/// Lays out the `visible` lines of `content` as screen rows.
+///
+/// With `width` of `None` every line is a single row.
+pub fn visual_rows(content: &str, visible: &[usize], width: Option<usize>) -> Vec<VisualRow> {
+    let mut rows = Vec::with_capacity(visible.len());
+    let mut visible = visible.iter().peekable();
+    let mut line_start = 0;
+
+    for (line_idx, line) in content.split('\n').enumerate() {
+        let start = line_start;
+        line_start += line.len() + 1;
+        if visible.next_if_eq(&&line_idx).is_none() {
+            continue;
+        }
+
+        let Some(width) = width.filter(|&width| line.len() > width) else {
+            rows.push(VisualRow {
+                line: line_idx,
+                start,
+                end: start + line.len(),
+                indent: 0,
+                last: true,
+            });
+            continue;
+        };
+        let wrapped = wrap_line(line, width);
+        let count = wrapped.len();
+        for (idx, (range, indent)) in wrapped.into_iter().enumerate() {
+            rows.push(VisualRow {
+                line: line_idx,
+                start: start + range.start,
+                end: start + range.end,
+                indent,
+                last: idx + 1 == count,
+            });
+        }
+    }
+
+    rows
+}
+
+/// Index of the row showing `offset`.
+///
+/// An offset at a wrap point belongs to the row it starts; offsets on hidden
+/// lines map to the last row before them.
+pub fn row_at(rows: &[VisualRow], offset: usize) -> usize {
+    rows.partition_point(|row| row.start <= offset)
+        .saturating_sub(1)
+}
+
+/// Columns continuation rows are indented by: the text after a list marker or
+/// blockquote prefix, or the line's own leading whitespace.
+fn hanging_indent(line: &str) -> usize {
+    str_width(&line[..MarkdownHighlighter::text_start(line)])
+}
+
+/// Columns `text` takes up.
+pub fn str_width(text: &str) -> usize {
+    text.chars().map(char_width).sum()
+}
+
+/// Columns `ch` takes up: two for wide East Asian characters and emoji, none
+/// for combining marks, joiners and variation selectors, one otherwise.
+pub fn char_width(ch: char) -> usize {
+    match ch as u32 {
+        0x0300..=0x036F
+        | 0x200B..=0x200F
+        | 0x20D0..=0x20FF
+        | 0xFE00..=0xFE0F
+        | 0xFE20..=0xFE2F
+        | 0xE0100..=0xE01EF => 0,
+        0x1100..=0x115F
+        | 0x2E80..=0x303E
+        | 0x3041..=0x33FF
+        | 0x3400..=0x4DBF
+        | 0x4E00..=0x9FFF
+        | 0xA000..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x1F300..=0x1F64F
+        | 0x1F680..=0x1F6FF
+        | 0x1F900..=0x1F9FF
+        | 0x1FA70..=0x1FAFF
+        | 0x20000..=0x3FFFD => 2,
+        _ => 1,
+    }
+}